@@ -1,3 +1,4 @@
+use rogalik::input::{Binding, InputMap, KeyCode};
 use rogalik::prelude::*;
 
 const WIDTH: u32 = 640;
@@ -25,6 +26,9 @@ const BALL_SPRITE: usize = 2;
 
 const MAX_LIVES: usize = 3;
 
+const UPDATE_RATE: u32 = 60;
+const FADE_TIME: f32 = 0.5;
+
 #[derive(Default)]
 struct GameState {
     lives: usize,
@@ -32,6 +36,7 @@ struct GameState {
     height: u32,
     paddle_origin: Vector2f,
     ball_origin: Option<Vector2f>,
+    prev_ball_origin: Option<Vector2f>,
     ball_velocity: Vector2f,
     blocks: [[bool; BLOCKS_VERTICAL]; BLOCKS_HORIZONTAL],
 }
@@ -51,6 +56,7 @@ impl Game for GameState {
                     cols: 4,
                     rows: 1,
                     padding: None,
+                    margin: None,
                 }),
                 diffuse_texture: sprite_texture,
                 ..Default::default()
@@ -64,6 +70,23 @@ impl Game for GameState {
 
         // Create camera
         context.graphics.create_camera(1., Vector2f::ZERO);
+
+        // Map the controls
+        context.input.set_input_map(
+            InputMap::new()
+                .with_axis(
+                    "move",
+                    &[
+                        Binding::Key(KeyCode::ArrowLeft),
+                        Binding::Key(KeyCode::KeyA),
+                    ],
+                    &[
+                        Binding::Key(KeyCode::ArrowRight),
+                        Binding::Key(KeyCode::KeyD),
+                    ],
+                )
+                .with_action("launch", &[Binding::Key(KeyCode::Space)]),
+        );
     }
 
     fn resize(&mut self, context: &mut Context) {
@@ -92,8 +115,8 @@ impl Scene for GameScene {
     fn enter(
         &mut self,
         game: &mut Self::Game,
-        context: &mut Context,
-        scenes: &mut SceneController<Self::Game>,
+        _context: &mut Context,
+        _scenes: &mut SceneController<Self::Game>,
     ) {
         // Reset blocks
         for col in game.blocks.iter_mut() {
@@ -117,25 +140,29 @@ impl Scene for GameScene {
         scenes: &mut SceneController<Self::Game>,
     ) {
         update_ball(game);
-        draw_world(&game, context);
-        draw_status(&game, context);
         handle_input(game, context);
 
         // Loose condition
         if game.lives == 0 {
-            scenes.switch(Box::new(EndScene("Game Over".to_string())));
+            scenes.switch_with(
+                Box::new(EndScene("Game Over".to_string())),
+                Transition::Fade(FADE_TIME),
+            );
         }
         // Win condition
-        if !game
-            .blocks
-            .iter()
-            .map(|col| col.iter())
-            .flatten()
-            .any(|b| *b)
-        {
-            scenes.switch(Box::new(EndScene("Congratulations!".to_string())));
+        if !game.blocks.iter().flat_map(|col| col.iter()).any(|b| *b) {
+            scenes.switch_with(
+                Box::new(EndScene("Congratulations!".to_string())),
+                Transition::Fade(FADE_TIME),
+            );
         }
     }
+
+    /// Render the game state
+    fn draw(&mut self, game: &Self::Game, context: &mut Context, alpha: f32) {
+        draw_world(game, context, alpha);
+        draw_status(game, context);
+    }
 }
 
 /// GameOver / Win scene
@@ -144,10 +171,17 @@ impl Scene for EndScene {
     type Game = GameState;
     fn update(
         &mut self,
-        game: &mut Self::Game,
+        _game: &mut Self::Game,
         context: &mut Context,
         scenes: &mut SceneController<Self::Game>,
     ) {
+        // Reset game on key press
+        if context.input.is_action_down("launch") {
+            scenes.switch_with(Box::new(GameScene), Transition::Wipe(FADE_TIME));
+        }
+    }
+
+    fn draw(&mut self, _game: &Self::Game, context: &mut Context, _alpha: f32) {
         let bounds = context.graphics.get_current_camera().get_bounds();
         let center = 0.5 * (bounds.0 + bounds.1);
         let width = context.graphics.text_dimensions("pixel", &self.0, 9.).x;
@@ -159,11 +193,6 @@ impl Scene for EndScene {
             9.,
             SpriteParams::default(),
         );
-
-        // Reset game on key press
-        if context.input.is_key_down(rogalik::input::KeyCode::Space) {
-            scenes.switch(Box::new(GameScene));
-        }
     }
 }
 
@@ -171,11 +200,13 @@ fn main() {
     let engine = EngineBuilder::new()
         .with_title("Arkanoid".to_string())
         .with_physical_size(WIDTH, HEIGHT)
+        .with_fixed_update(UPDATE_RATE)
         .build(GameState::default(), Box::new(GameScene));
     engine.run();
 }
 
 fn update_ball(game: &mut GameState) {
+    game.prev_ball_origin = game.ball_origin;
     let Some(origin) = game.ball_origin.as_mut() else {
         return;
     };
@@ -226,10 +257,12 @@ fn update_ball(game: &mut GameState) {
     }
 }
 
-fn draw_world(game: &GameState, context: &mut Context) {
+fn draw_world(game: &GameState, context: &mut Context, alpha: f32) {
     draw_paddle(game.paddle_origin, context);
     if let Some(origin) = game.ball_origin {
-        draw_ball(origin, context);
+        // Interpolate between the last two updates for a smooth movement
+        let prev = game.prev_ball_origin.unwrap_or(origin);
+        draw_ball(prev.lerp(&origin, alpha), context);
     }
     draw_blocks(game, context);
 }
@@ -296,22 +329,11 @@ fn draw_status(game: &GameState, context: &mut Context) {
 
 fn handle_input(game: &mut GameState, context: &mut Context) {
     // Paddle movement
-    if context
-        .input
-        .is_key_down(rogalik::input::KeyCode::ArrowLeft)
-    {
-        game.paddle_origin.x = 0.0_f32.max(game.paddle_origin.x - PADDLE_MOVE);
-    }
-    if context
-        .input
-        .is_key_down(rogalik::input::KeyCode::ArrowRight)
-    {
-        game.paddle_origin.x =
-            (game.width as f32 - PADDLE_WIDTH).min(game.paddle_origin.x + PADDLE_MOVE);
-    }
+    game.paddle_origin.x = (game.paddle_origin.x + PADDLE_MOVE * context.input.get_axis("move"))
+        .clamp(0., game.width as f32 - PADDLE_WIDTH);
 
     // Ball release
-    if game.ball_origin.is_none() && context.input.is_key_pressed(rogalik::input::KeyCode::Space) {
+    if game.ball_origin.is_none() && context.input.is_action_pressed("launch") {
         game.ball_origin = Some(Vector2f::new(
             game.paddle_origin.x + 0.5 * (PADDLE_WIDTH - BALL_SIZE),
             game.paddle_origin.y + PADDLE_HEIGHT,
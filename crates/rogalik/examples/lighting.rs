@@ -30,6 +30,7 @@ impl Game for GameState {
                     cols: 1,
                     rows: 1,
                     padding: None,
+                    margin: None,
                 }),
                 diffuse_texture,
                 normal_texture,
@@ -77,8 +78,9 @@ impl Scene for MainScene {
             }
         }
 
-        let mouse = context.input.get_mouse_physical_position();
-        let mouse_world = context.graphics.get_current_camera().camera_to_world(mouse);
+        let mouse_world = context
+            .input
+            .get_mouse_world_position(context.graphics.get_current_camera());
 
         // Dynamic light
         let _ = context
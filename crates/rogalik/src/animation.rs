@@ -0,0 +1,446 @@
+#[cfg(feature = "aseprite")]
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[cfg(feature = "aseprite")]
+use rogalik_assets::AssetContext;
+#[cfg(feature = "aseprite")]
+use rogalik_common::EngineError;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AnimationMode {
+    #[default]
+    Loop,
+    /// Plays forward and back, without repeating the edge frames.
+    PingPong,
+    /// Stops at the last frame.
+    Once,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AnimationFrame {
+    /// Atlas index of the frame.
+    pub index: usize,
+    /// Duration in seconds.
+    pub duration: f32,
+    /// Event emitted when the frame is entered (eg. a footstep sound).
+    pub event: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AnimationClip {
+    pub frames: Vec<AnimationFrame>,
+    pub mode: AnimationMode,
+}
+impl AnimationClip {
+    pub fn new(mode: AnimationMode) -> Self {
+        Self {
+            mode,
+            ..Default::default()
+        }
+    }
+    pub fn with_frame(mut self, index: usize, duration: f32) -> Self {
+        self.frames.push(AnimationFrame {
+            index,
+            duration,
+            event: None,
+        });
+        self
+    }
+    /// Adds the frames, each with the same duration.
+    pub fn with_frames(mut self, indices: impl IntoIterator<Item = usize>, duration: f32) -> Self {
+        for index in indices {
+            self = self.with_frame(index, duration);
+        }
+        self
+    }
+    /// Sets the event of the `frame` (position within the clip).
+    pub fn with_event(mut self, frame: usize, event: &str) -> Self {
+        if let Some(frame) = self.frames.get_mut(frame) {
+            frame.event = Some(event.to_string());
+        }
+        self
+    }
+    /// Duration of a single playthrough, in seconds.
+    pub fn get_duration(&self) -> f32 {
+        self.frames.iter().map(|f| f.duration).sum()
+    }
+}
+
+/// Set of named animation clips (eg. `idle`, `walk`) of a single sprite atlas.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Animation {
+    pub clips: HashMap<String, AnimationClip>,
+}
+impl Animation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_clip(mut self, name: &str, clip: AnimationClip) -> Self {
+        self.clips.insert(name.to_string(), clip);
+        self
+    }
+    pub fn get_clip(&self, name: &str) -> Option<&AnimationClip> {
+        self.clips.get(name)
+    }
+    /// Loads the clips from an Aseprite JSON export (see `from_aseprite`).
+    #[cfg(feature = "aseprite")]
+    pub fn load_aseprite(store: &mut impl AssetContext, path: &str) -> Result<Self, EngineError> {
+        let id = store.load(path)?;
+        let asset = store.get(id).ok_or(EngineError::ResourceNotFound)?;
+        let animation = Self::from_aseprite(asset.data.get());
+        if animation.is_err() {
+            log::error!("Can't parse the Aseprite animation: {}", path);
+        }
+        animation
+    }
+    /// Parses an Aseprite JSON export (with the array or hash frames).
+    /// Every frame tag becomes a clip, or a single `default` clip is created
+    /// if there are no tags. Atlas indices are read from the frame positions,
+    /// so the sheet should be a regular grid (as the material's atlas).
+    /// Hash exports are ordered by the (last) number in the frame names.
+    #[cfg(feature = "aseprite")]
+    pub fn from_aseprite(data: &[u8]) -> Result<Self, EngineError> {
+        let root: Value = serde_json::from_slice(data).map_err(|_| EngineError::InvalidResource)?;
+        let frames = match root.get("frames") {
+            Some(Value::Array(frames)) => frames.iter().collect::<Vec<_>>(),
+            Some(Value::Object(frames)) => {
+                let mut frames = frames.iter().collect::<Vec<_>>();
+                frames.sort_by_key(|(name, _)| get_frame_number(name));
+                frames.into_iter().map(|(_, f)| f).collect()
+            }
+            _ => return Err(EngineError::InvalidResource),
+        };
+        let sheet_size = (
+            get_number(&root, "/meta/size/w"),
+            get_number(&root, "/meta/size/h"),
+        );
+        let frames = frames
+            .iter()
+            .map(|f| get_aseprite_frame(f, sheet_size))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(EngineError::InvalidResource)?;
+
+        let tags = root
+            .pointer("/meta/frameTags")
+            .and_then(Value::as_array)
+            .map(|t| t.as_slice())
+            .unwrap_or_default();
+        if tags.is_empty() {
+            let clip = AnimationClip {
+                frames,
+                mode: AnimationMode::Loop,
+            };
+            return Ok(Self::new().with_clip("default", clip));
+        }
+
+        let mut animation = Self::new();
+        for tag in tags {
+            let name = tag.get("name").and_then(Value::as_str).unwrap_or_default();
+            let from = tag.get("from").and_then(Value::as_u64).unwrap_or(0) as usize;
+            let to = tag.get("to").and_then(Value::as_u64).unwrap_or(0) as usize;
+            let mut clip_frames = frames
+                .get(from..=to)
+                .ok_or(EngineError::InvalidResource)?
+                .to_vec();
+            let direction = tag
+                .get("direction")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            if direction.ends_with("reverse") {
+                clip_frames.reverse();
+            }
+            let mode = match (direction, tag.get("repeat").and_then(Value::as_str)) {
+                ("pingpong" | "pingpong_reverse", _) => AnimationMode::PingPong,
+                (_, Some("1")) => AnimationMode::Once,
+                _ => AnimationMode::Loop,
+            };
+            animation.clips.insert(
+                name.to_string(),
+                AnimationClip {
+                    frames: clip_frames,
+                    mode,
+                },
+            );
+        }
+        Ok(animation)
+    }
+}
+
+/// Plays an animation clip, advanced by the frame delta.
+/// The current atlas index is meant to be passed to `draw_atlas_sprite`.
+#[derive(Clone, Debug)]
+pub struct Animator {
+    clip: AnimationClip,
+    frame: usize,
+    elapsed: f32,
+    // -1 when playing backwards (in the ping-pong mode)
+    direction: isize,
+    finished: bool,
+    // positions of the frames entered during the last update
+    events: Vec<usize>,
+    started: bool,
+    /// Playback rate multiplier.
+    pub speed: f32,
+}
+impl Animator {
+    pub fn new(clip: &AnimationClip) -> Self {
+        let mut animator = Self {
+            clip: AnimationClip::default(),
+            frame: 0,
+            elapsed: 0.,
+            direction: 1,
+            finished: false,
+            events: Vec::new(),
+            started: false,
+            speed: 1.,
+        };
+        animator.play(clip);
+        animator
+    }
+    /// Starts the clip from its first frame.
+    pub fn play(&mut self, clip: &AnimationClip) {
+        self.clip = clip.clone();
+        self.frame = 0;
+        self.elapsed = 0.;
+        self.direction = 1;
+        self.finished = false;
+        self.events.clear();
+        self.started = false;
+    }
+    /// Advances the animation by `delta` seconds.
+    /// Usually called in each update with `Time::get_delta`.
+    pub fn update(&mut self, delta: f32) {
+        self.events.clear();
+        if self.clip.frames.is_empty() {
+            return;
+        }
+        if !self.started {
+            // the first frame's event
+            self.started = true;
+            self.events.push(self.frame);
+        }
+        // zero length clips would never advance
+        if self.finished || self.clip.get_duration() <= 0. {
+            return;
+        }
+        self.elapsed += delta * self.speed;
+        // the extra whole cycles are skipped (eg. after a long pause), so an
+        // update goes through two cycles at most (and emits their events)
+        if let Some(cycle) = self.get_cycle_duration() {
+            if self.elapsed >= 2. * cycle {
+                self.elapsed = cycle + self.elapsed % cycle;
+            }
+        }
+        while self.elapsed >= self.clip.frames[self.frame].duration {
+            self.elapsed -= self.clip.frames[self.frame].duration;
+            if !self.advance() {
+                self.elapsed = 0.;
+                break;
+            }
+            self.events.push(self.frame);
+        }
+    }
+    /// Atlas index of the current frame.
+    pub fn get_index(&self) -> usize {
+        self.clip
+            .frames
+            .get(self.frame)
+            .map(|f| f.index)
+            .unwrap_or_default()
+    }
+    /// Position of the current frame within the clip.
+    pub fn get_frame(&self) -> usize {
+        self.frame
+    }
+    /// Events of the frames entered during the last update.
+    pub fn get_events(&self) -> impl Iterator<Item = &str> {
+        self.events
+            .iter()
+            .filter_map(|&f| self.clip.frames[f].event.as_deref())
+    }
+    /// Whether a `Once` clip has reached its end.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+    // time after which a repeating clip returns to the same frame
+    // (and direction), `None` for the `Once` clips
+    fn get_cycle_duration(&self) -> Option<f32> {
+        let frames = &self.clip.frames;
+        match self.clip.mode {
+            AnimationMode::Once => None,
+            AnimationMode::Loop => Some(self.clip.get_duration()),
+            AnimationMode::PingPong if frames.len() == 1 => Some(frames[0].duration),
+            AnimationMode::PingPong => Some(
+                // the end frames are played once per cycle, the others twice
+                2. * self.clip.get_duration()
+                    - frames[0].duration
+                    - frames[frames.len() - 1].duration,
+            ),
+        }
+    }
+    // returns false when the clip is finished
+    fn advance(&mut self) -> bool {
+        let len = self.clip.frames.len();
+        match self.clip.mode {
+            AnimationMode::Loop => self.frame = (self.frame + 1) % len,
+            AnimationMode::Once => {
+                if self.frame + 1 >= len {
+                    self.finished = true;
+                    return false;
+                }
+                self.frame += 1;
+            }
+            AnimationMode::PingPong => {
+                if len == 1 {
+                    return true;
+                }
+                let next = self.frame as isize + self.direction;
+                if next < 0 || next >= len as isize {
+                    self.direction = -self.direction;
+                }
+                self.frame = (self.frame as isize + self.direction) as usize;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(feature = "aseprite")]
+fn get_aseprite_frame(frame: &Value, sheet_size: (f64, f64)) -> Option<AnimationFrame> {
+    let (x, y) = (get_number(frame, "/frame/x"), get_number(frame, "/frame/y"));
+    let (w, h) = (get_number(frame, "/frame/w"), get_number(frame, "/frame/h"));
+    if w <= 0. || h <= 0. {
+        return None;
+    }
+    // grid cells, including the possible padding
+    let cols = (sheet_size.0 / w).floor().max(1.);
+    let rows = (sheet_size.1 / h).floor().max(1.);
+    let col = (x * cols / sheet_size.0.max(w)).floor();
+    let row = (y * rows / sheet_size.1.max(h)).floor();
+    Some(AnimationFrame {
+        index: (row * cols + col) as usize,
+        // in milliseconds
+        duration: frame.get("duration")?.as_f64()? as f32 / 1000.,
+        event: None,
+    })
+}
+
+#[cfg(feature = "aseprite")]
+fn get_number(value: &Value, pointer: &str) -> f64 {
+    value
+        .pointer(pointer)
+        .and_then(Value::as_f64)
+        .unwrap_or_default()
+}
+
+#[cfg(feature = "aseprite")]
+fn get_frame_number(name: &str) -> usize {
+    name.split(|c: char| !c.is_ascii_digit())
+        .rfind(|s| !s.is_empty())
+        .and_then(|s| s.parse().ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(animator: &mut Animator, steps: usize) -> Vec<usize> {
+        (0..steps)
+            .map(|_| {
+                animator.update(0.1);
+                animator.get_index()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn modes() {
+        let clip = AnimationClip::new(AnimationMode::Loop).with_frames(0..3, 0.1);
+        let mut animator = Animator::new(&clip);
+        assert_eq!(step(&mut animator, 4), [1, 2, 0, 1]);
+
+        animator.play(&AnimationClip {
+            mode: AnimationMode::PingPong,
+            ..clip.clone()
+        });
+        assert_eq!(step(&mut animator, 6), [1, 2, 1, 0, 1, 2]);
+
+        animator.play(&AnimationClip {
+            mode: AnimationMode::Once,
+            ..clip
+        });
+        assert_eq!(step(&mut animator, 4), [1, 2, 2, 2]);
+        assert!(animator.is_finished());
+    }
+
+    #[test]
+    fn frame_events() {
+        let clip = AnimationClip::new(AnimationMode::Loop)
+            .with_frame(4, 0.5)
+            .with_frame(5, 0.25)
+            .with_event(0, "start")
+            .with_event(1, "step");
+        let mut animator = Animator::new(&clip);
+        animator.update(0.);
+        assert_eq!(animator.get_events().collect::<Vec<_>>(), ["start"]);
+        // both frames entered within a single update
+        animator.update(0.8);
+        assert_eq!(animator.get_events().collect::<Vec<_>>(), ["step", "start"]);
+        assert_eq!(animator.get_index(), 4);
+        animator.update(0.1);
+        assert_eq!(animator.get_events().count(), 0);
+    }
+
+    #[test]
+    fn long_delta() {
+        let clip = AnimationClip::new(AnimationMode::Loop)
+            .with_frames(0..3, 0.1)
+            .with_event(1, "step");
+        let mut animator = Animator::new(&clip);
+        animator.update(0.);
+        // 1000 cycles and a half frame
+        animator.update(300.15);
+        assert_eq!(animator.get_frame(), 1);
+        assert_eq!(animator.get_events().count(), 2);
+
+        // 0, 1, 2, 1 per cycle
+        animator.play(&AnimationClip {
+            mode: AnimationMode::PingPong,
+            ..clip
+        });
+        animator.update(0.);
+        animator.update(4000.25);
+        assert_eq!(animator.get_frame(), 2);
+        assert!(animator.get_events().count() <= 4);
+    }
+
+    #[test]
+    #[cfg(feature = "aseprite")]
+    fn aseprite() {
+        let data = r#"{
+ "frames": {
+  "hero 10.aseprite": {"frame": {"x": 32, "y": 16, "w": 16, "h": 16}, "duration": 200},
+  "hero 2.aseprite": {"frame": {"x": 32, "y": 0, "w": 16, "h": 16}, "duration": 100},
+  "hero 0.aseprite": {"frame": {"x": 0, "y": 0, "w": 16, "h": 16}, "duration": 100}
+ },
+ "meta": {
+  "size": {"w": 64, "h": 32},
+  "frameTags": [
+   {"name": "idle", "from": 0, "to": 1, "direction": "pingpong"},
+   {"name": "die", "from": 1, "to": 2, "direction": "reverse", "repeat": "1"}
+  ]
+ }
+}"#;
+        let animation = Animation::from_aseprite(data.as_bytes()).unwrap();
+        let idle = animation.get_clip("idle").unwrap();
+        assert_eq!(idle.mode, AnimationMode::PingPong);
+        assert_eq!(idle.frames[1].index, 2);
+
+        let die = animation.get_clip("die").unwrap();
+        assert_eq!(die.mode, AnimationMode::Once);
+        assert_eq!(die.frames[0].index, 6);
+        assert_eq!(die.frames[0].duration, 0.2);
+    }
+}
@@ -1,7 +1,6 @@
-use rogalik_wgpu::WgpuContext;
 use std::sync::Arc;
 use winit::application::ApplicationHandler;
-use winit::dpi::PhysicalSize;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
 use winit::event::{ElementState, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
 use winit::keyboard::PhysicalKey;
@@ -9,13 +8,10 @@ use winit::window::{Window, WindowAttributes, WindowId};
 
 use crate::{
     engine::Context,
-    scenes::{update_scenes, SceneManager},
+    scenes::{draw_scenes, update_scenes, SceneManager},
     Game, Scene,
 };
-use rogalik_common::{
-    traits::{AudioSetup, GraphicsSetup},
-    GraphicsContext,
-};
+use rogalik_common::traits::{AudioSetup, GraphicsSetup};
 
 pub struct App<T> {
     pub context: Context,
@@ -42,6 +38,23 @@ impl<T: Game> App<T> {
     fn set_inner_size_on_resume(&mut self) {
         self.context.inner_size = self.window.as_ref().expect("No valid window!").inner_size();
     }
+    /// Applies the window changes requested by the input context.
+    fn handle_input_requests(&mut self) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        if let Some(allowed) = self.context.input.take_text_input_request() {
+            window.set_ime_allowed(allowed);
+        }
+        if let Some((position, size)) = self.context.input.take_ime_cursor_area_request() {
+            // input uses the bottom-left origin, winit the top-left one
+            let y = self.context.inner_size.height as f32 - position.y - size.y;
+            window.set_ime_cursor_area(
+                PhysicalPosition::new(position.x, y),
+                PhysicalSize::new(size.x, size.y),
+            );
+        }
+    }
     fn resize(&mut self, physical_size: PhysicalSize<u32>) {
         self.context.inner_size = physical_size;
         self.context
@@ -111,9 +124,27 @@ impl<T: Game> ApplicationHandler for App<T> {
                     }
                 }
             }
+            WindowEvent::Ime(ime) => {
+                self.context.input.handle_ime(&ime);
+            }
             WindowEvent::MouseInput { button, state, .. } => {
                 self.context.input.handle_mouse_button(&button, &state);
             }
+            WindowEvent::MouseWheel { delta, .. } => {
+                self.context.input.handle_mouse_wheel(&delta);
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.context.input.handle_modifiers(&modifiers);
+            }
+            WindowEvent::Focused(focused) => {
+                self.context.input.handle_focus(focused);
+            }
+            WindowEvent::CursorEntered { .. } => {
+                self.context.input.handle_cursor_entered();
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.context.input.handle_cursor_left();
+            }
             WindowEvent::CursorMoved { position, .. } => {
                 self.context
                     .input
@@ -149,14 +180,32 @@ impl<T: Game> ApplicationHandler for App<T> {
             }
             WindowEvent::RedrawRequested => {
                 // let start = std::time::Instant::now();
-                self.context.time.update();
+                match self.context.input.replay_frame() {
+                    Some(delta) => self.context.time.update_with_delta(delta),
+                    None => self.context.time.update(),
+                }
+                self.context
+                    .graphics
+                    .update_time(self.context.time.get_frame_delta());
+
+                self.context.input.poll_gamepads();
+                self.context
+                    .input
+                    .update(self.context.time.get_frame_delta());
+                for _ in 0..self.context.time.take_fixed_steps() {
+                    self.context.time.fixed_tick();
+                    update_scenes(&mut self.scene_manager, &mut self.game, &mut self.context);
+                    // input events should be consumed by a single update only.
+                    // if no update has been executed they are kept for the next frame
+                    self.context.input.clear();
+                }
                 self.context
                     .graphics
-                    .update_time(self.context.time.get_delta());
-                update_scenes(&mut self.scene_manager, &mut self.game, &mut self.context);
+                    .update_cameras(self.context.time.get_frame_delta());
+                draw_scenes(&mut self.scene_manager, &self.game, &mut self.context);
+                self.handle_input_requests();
 
                 self.context.graphics.render();
-                self.context.input.clear();
                 // println!(
                 //     "{} {}",
                 //     1. / start.elapsed().as_secs_f32(),
@@ -53,6 +53,7 @@ pub struct EngineBuilder {
     resizable: bool,
     fullscreen: bool,
     audio_params: Option<AudioDeviceParams>,
+    fixed_update: Option<u32>,
 }
 impl EngineBuilder {
     pub fn new() -> Self {
@@ -82,6 +83,28 @@ impl EngineBuilder {
         self.audio_params = Some(params);
         self
     }
+    /// Runs `Scene::update` at a fixed rate (in Hz), independent of the frame rate.
+    /// The update can then be executed zero or more times per frame.
+    /// `Time::get_alpha` should be used to interpolate the rendered state.
+    pub fn with_fixed_update(mut self, hz: u32) -> Self {
+        self.fixed_update = Some(hz);
+        self
+    }
+    fn get_time(&self) -> Time {
+        match self.fixed_update {
+            Some(hz) => Time::with_fixed_step(1. / hz.max(1) as f32),
+            None => Time::new(),
+        }
+    }
+    fn get_input(&self) -> InputContext {
+        #[allow(unused_mut)]
+        let mut input = InputContext::new();
+        #[cfg(feature = "gilrs")]
+        if let Some(backend) = crate::input::GilrsBackend::new() {
+            input.set_gamepad_backend(Box::new(backend));
+        }
+        input
+    }
     pub fn build<T>(&self, game: T, scene: Box<dyn Scene<Game = T>>) -> Engine<T>
     where
         T: Game + 'static,
@@ -117,8 +140,8 @@ impl EngineBuilder {
             assets,
             audio,
             graphics,
-            input: InputContext::new(),
-            time: Time::new(),
+            input: self.get_input(),
+            time: self.get_time(),
             inner_size: PhysicalSize::default(),
             scale_factor: 1.,
             os_path: None,
@@ -144,8 +167,8 @@ impl EngineBuilder {
             assets,
             audio,
             graphics,
-            input: InputContext::new(),
-            time: Time::new(),
+            input: self.get_input(),
+            time: self.get_time(),
             inner_size: PhysicalSize::default(),
             scale_factor: 1.,
             os_path: None,
@@ -200,8 +223,8 @@ impl EngineBuilder {
             assets,
             audio,
             graphics,
-            input: InputContext::new(),
-            time: Time::new(),
+            input: self.get_input(),
+            time: self.get_time(),
             inner_size: PhysicalSize::default(),
             scale_factor: 1.,
             os_path,
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use super::{GamepadAxis, GamepadButton, KeyCode, MouseButton};
+
+/// A single physical input that can trigger an action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// Any touch on the screen.
+    Touch,
+    /// Button of any connected gamepad.
+    GamepadButton(GamepadButton),
+}
+
+/// Axis, built from two sets of digital bindings and optional analog inputs.
+/// Digital bindings evaluate to -1. when a negative binding is down,
+/// 1. when a positive one is down and 0. otherwise.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Axis {
+    pub negative: Vec<Binding>,
+    pub positive: Vec<Binding>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub analog: Vec<GamepadAxis>,
+}
+
+/// Named actions and axes mapped to the physical inputs.
+/// The map can be modified at runtime (eg. in a key remapping menu)
+/// and, with the `serialize` feature enabled, stored via `rogalik_persist`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct InputMap {
+    actions: HashMap<String, Vec<Binding>>,
+    axes: HashMap<String, Axis>,
+}
+impl InputMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds a binding to the action. Creates the action if it does not exist.
+    pub fn bind_action(&mut self, action: &str, binding: Binding) {
+        let bindings = self.actions.entry(action.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+    /// Builder style version of `bind_action`.
+    pub fn with_action(mut self, action: &str, bindings: &[Binding]) -> Self {
+        for binding in bindings {
+            self.bind_action(action, *binding);
+        }
+        self
+    }
+    /// Replaces all the bindings of the action.
+    pub fn set_action_bindings(&mut self, action: &str, bindings: Vec<Binding>) {
+        self.actions.insert(action.to_string(), bindings);
+    }
+    pub fn unbind_action(&mut self, action: &str, binding: Binding) {
+        if let Some(bindings) = self.actions.get_mut(action) {
+            bindings.retain(|b| *b != binding);
+        }
+    }
+    pub fn remove_action(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+    pub fn get_action_bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map_or(&[], |b| b.as_slice())
+    }
+    /// Returns the names of all the actions bound to the input.
+    /// Useful to detect conflicts when rebinding.
+    pub fn get_actions_for(&self, binding: Binding) -> Vec<&str> {
+        self.actions
+            .iter()
+            .filter(|(_, b)| b.contains(&binding))
+            .map(|(a, _)| a.as_str())
+            .collect()
+    }
+    pub fn set_axis(&mut self, axis: &str, negative: Vec<Binding>, positive: Vec<Binding>) {
+        let analog = self.axes.remove(axis).map(|a| a.analog).unwrap_or_default();
+        self.axes.insert(
+            axis.to_string(),
+            Axis {
+                negative,
+                positive,
+                analog,
+            },
+        );
+    }
+    /// Adds an analog gamepad input to the axis. Creates the axis if it does
+    /// not exist.
+    pub fn bind_analog_axis(&mut self, axis: &str, analog: GamepadAxis) {
+        let axis = self.axes.entry(axis.to_string()).or_default();
+        if !axis.analog.contains(&analog) {
+            axis.analog.push(analog);
+        }
+    }
+    /// Builder style version of `bind_analog_axis`.
+    pub fn with_analog_axis(mut self, axis: &str, analog: GamepadAxis) -> Self {
+        self.bind_analog_axis(axis, analog);
+        self
+    }
+    /// Builder style version of `set_axis`.
+    pub fn with_axis(mut self, axis: &str, negative: &[Binding], positive: &[Binding]) -> Self {
+        self.set_axis(axis, negative.to_vec(), positive.to_vec());
+        self
+    }
+    pub fn get_axis(&self, axis: &str) -> Option<&Axis> {
+        self.axes.get(axis)
+    }
+    pub fn get_axis_mut(&mut self, axis: &str) -> Option<&mut Axis> {
+        self.axes.get_mut(axis)
+    }
+    pub fn remove_axis(&mut self, axis: &str) {
+        self.axes.remove(axis);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::event::ElementState;
+
+    use super::*;
+    use crate::input::InputContext;
+
+    fn input() -> InputContext {
+        let mut input = InputContext::new();
+        input.set_input_map(
+            InputMap::new()
+                .with_action(
+                    "jump",
+                    &[
+                        Binding::Key(KeyCode::Space),
+                        Binding::Mouse(MouseButton::Left),
+                    ],
+                )
+                .with_axis(
+                    "move_x",
+                    &[
+                        Binding::Key(KeyCode::ArrowLeft),
+                        Binding::Key(KeyCode::KeyA),
+                    ],
+                    &[
+                        Binding::Key(KeyCode::ArrowRight),
+                        Binding::Key(KeyCode::KeyD),
+                    ],
+                )
+                .with_axis(
+                    "move_y",
+                    &[Binding::Key(KeyCode::ArrowDown)],
+                    &[Binding::Key(KeyCode::ArrowUp)],
+                ),
+        );
+        input
+    }
+
+    #[test]
+    fn action_any_binding() {
+        let mut input = input();
+        assert!(!input.is_action_down("jump"));
+
+        input.handle_mouse_button(&MouseButton::Left, &ElementState::Pressed);
+        assert!(input.is_action_down("jump"));
+        assert!(input.is_action_pressed("jump"));
+
+        input.clear();
+        assert!(input.is_action_down("jump"));
+        assert!(!input.is_action_pressed("jump"));
+
+        input.handle_mouse_button(&MouseButton::Left, &ElementState::Released);
+        assert!(!input.is_action_down("jump"));
+        assert!(input.is_action_released("jump"));
+        assert!(!input.is_action_down("unknown"));
+    }
+
+    #[test]
+    fn rebind_action() {
+        let mut input = input();
+        input.keys_down.insert(KeyCode::Enter);
+        assert!(!input.is_action_down("jump"));
+
+        let map = input.get_input_map_mut();
+        map.unbind_action("jump", Binding::Key(KeyCode::Space));
+        map.bind_action("jump", Binding::Key(KeyCode::Enter));
+        assert_eq!(map.get_actions_for(Binding::Key(KeyCode::Enter)), ["jump"]);
+        assert!(input.is_action_down("jump"));
+    }
+
+    #[test]
+    fn axes() {
+        let mut input = input();
+        assert_eq!(input.get_axis("move_x"), 0.);
+
+        input.keys_down.insert(KeyCode::KeyA);
+        assert_eq!(input.get_axis("move_x"), -1.);
+
+        input.keys_down.insert(KeyCode::ArrowRight);
+        assert_eq!(input.get_axis("move_x"), 0.);
+
+        input.keys_down.remove(&KeyCode::KeyA);
+        input.keys_down.insert(KeyCode::ArrowUp);
+        let v = input.get_axis_vector("move_x", "move_y");
+        assert!((v.len() - 1.).abs() < 1e-5);
+        assert!((v.x - v.y).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::HashSet;
+
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use rogalik_math::vectors::Vector2f;
+
+pub const DEFAULT_STICK_DEADZONE: f32 = 0.15;
+pub const DEFAULT_TRIGGER_DEADZONE: f32 = 0.05;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct GamepadId(pub usize);
+
+/// Gamepad buttons, named by their position (Xbox A is `South` etc.)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftStick,
+    RightStick,
+    Select,
+    Start,
+    Mode,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum GamepadStick {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum GamepadTrigger {
+    Left,
+    Right,
+}
+
+/// Single analog value of a gamepad, used by the input map axes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum GamepadEvent {
+    Connected(GamepadId),
+    Disconnected(GamepadId),
+    ButtonPressed(GamepadId, GamepadButton),
+    ButtonReleased(GamepadId, GamepadButton),
+    /// Raw stick position, each component in the -1. to 1. range.
+    StickMoved(GamepadId, GamepadStick, Vector2f),
+    /// Raw trigger value in the 0. to 1. range.
+    TriggerMoved(GamepadId, GamepadTrigger, f32),
+}
+
+/// Source of the gamepad events, polled once per frame.
+/// With the `gilrs` feature the engine installs `GilrsBackend` by default.
+/// Otherwise games can provide their own and tests can inject a fake
+/// controller.
+pub trait GamepadBackend {
+    /// Returns the events that occurred since the last poll.
+    fn poll(&mut self) -> Vec<GamepadEvent>;
+}
+
+#[derive(Default)]
+pub(crate) struct GamepadState {
+    pub connected: bool,
+    pub buttons_down: HashSet<GamepadButton>,
+    pub buttons_pressed: HashSet<GamepadButton>,
+    pub buttons_released: HashSet<GamepadButton>,
+    pub sticks: [Vector2f; 2],
+    pub triggers: [f32; 2],
+}
+impl GamepadState {
+    pub fn new() -> Self {
+        Self {
+            connected: true,
+            ..Default::default()
+        }
+    }
+    pub fn clear(&mut self) {
+        self.buttons_pressed.clear();
+        self.buttons_released.clear();
+    }
+    pub fn press(&mut self, button: GamepadButton) {
+        if self.buttons_down.insert(button) {
+            self.buttons_pressed.insert(button);
+        }
+    }
+    pub fn release(&mut self, button: GamepadButton) {
+        if self.buttons_down.remove(&button) {
+            self.buttons_released.insert(button);
+        }
+    }
+    pub fn disconnect(&mut self) {
+        self.connected = false;
+        for button in self.buttons_down.drain() {
+            self.buttons_released.insert(button);
+        }
+        self.sticks = [Vector2f::ZERO; 2];
+        self.triggers = [0.; 2];
+    }
+}
+
+pub(crate) fn stick_index(stick: GamepadStick) -> usize {
+    match stick {
+        GamepadStick::Left => 0,
+        GamepadStick::Right => 1,
+    }
+}
+
+pub(crate) fn trigger_index(trigger: GamepadTrigger) -> usize {
+    match trigger {
+        GamepadTrigger::Left => 0,
+        GamepadTrigger::Right => 1,
+    }
+}
+
+/// Radial deadzone, the remaining range is rescaled to start at 0.
+pub(crate) fn apply_stick_deadzone(v: Vector2f, deadzone: f32) -> Vector2f {
+    let len = v.len();
+    if len <= deadzone || len == 0. {
+        return Vector2f::ZERO;
+    }
+    let scaled = ((len - deadzone) / (1. - deadzone)).min(1.);
+    v * (scaled / len)
+}
+
+pub(crate) fn apply_trigger_deadzone(v: f32, deadzone: f32) -> f32 {
+    if v <= deadzone {
+        return 0.;
+    }
+    ((v - deadzone) / (1. - deadzone)).min(1.)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::input::{Binding, InputContext, InputMap};
+
+    // Fake controller, events are pushed by the test
+    #[derive(Clone, Default)]
+    struct FakeBackend(Rc<RefCell<Vec<GamepadEvent>>>);
+    impl FakeBackend {
+        fn send(&self, event: GamepadEvent) {
+            self.0.borrow_mut().push(event);
+        }
+    }
+    impl GamepadBackend for FakeBackend {
+        fn poll(&mut self) -> Vec<GamepadEvent> {
+            self.0.take()
+        }
+    }
+
+    fn input() -> (InputContext, FakeBackend) {
+        let backend = FakeBackend::default();
+        let mut input = InputContext::new();
+        input.set_gamepad_backend(Box::new(backend.clone()));
+        (input, backend)
+    }
+
+    #[test]
+    fn connection_events() {
+        let (mut input, backend) = input();
+        let id = GamepadId(1);
+        backend.send(GamepadEvent::Connected(id));
+        input.poll_gamepads();
+        assert_eq!(input.get_gamepads_connected(), [id]);
+        assert!(input.is_gamepad_connected(id));
+
+        // repeated connection events keep the state
+        backend.send(GamepadEvent::ButtonPressed(id, GamepadButton::South));
+        backend.send(GamepadEvent::Connected(id));
+        input.poll_gamepads();
+        assert_eq!(input.get_gamepads_connected(), [id]);
+        assert!(input.is_gamepad_button_down(id, GamepadButton::South));
+
+        input.clear();
+        assert!(input.get_gamepads_connected().is_empty());
+        assert!(input.is_gamepad_connected(id));
+
+        backend.send(GamepadEvent::Disconnected(id));
+        input.poll_gamepads();
+        assert!(!input.is_gamepad_connected(id));
+        assert_eq!(input.get_gamepads_disconnected().collect::<Vec<_>>(), [id]);
+        // held buttons are released on disconnect
+        assert!(input.is_gamepad_button_released(id, GamepadButton::South));
+
+        input.clear();
+        assert_eq!(input.get_gamepads_disconnected().count(), 0);
+        assert_eq!(input.get_gamepads().count(), 0);
+    }
+
+    #[test]
+    fn button_frame_semantics() {
+        let (mut input, backend) = input();
+        let id = GamepadId(0);
+        backend.send(GamepadEvent::Connected(id));
+        backend.send(GamepadEvent::ButtonPressed(id, GamepadButton::North));
+        input.poll_gamepads();
+        assert!(input.is_gamepad_button_pressed(id, GamepadButton::North));
+        assert!(input.is_gamepad_button_down(id, GamepadButton::North));
+
+        input.clear();
+        input.poll_gamepads();
+        assert!(!input.is_gamepad_button_pressed(id, GamepadButton::North));
+        assert!(input.is_gamepad_button_down(id, GamepadButton::North));
+
+        backend.send(GamepadEvent::ButtonReleased(id, GamepadButton::North));
+        input.poll_gamepads();
+        assert!(input.is_gamepad_button_released(id, GamepadButton::North));
+        assert!(!input.is_gamepad_button_down(id, GamepadButton::North));
+    }
+
+    #[test]
+    fn deadzones() {
+        let (mut input, backend) = input();
+        let id = GamepadId(0);
+        backend.send(GamepadEvent::StickMoved(
+            id,
+            GamepadStick::Left,
+            Vector2f::new(0.1, 0.),
+        ));
+        backend.send(GamepadEvent::TriggerMoved(id, GamepadTrigger::Right, 1.));
+        input.poll_gamepads();
+        // registered on the first event
+        assert!(input.is_gamepad_connected(id));
+        assert_eq!(
+            input.get_gamepad_stick(id, GamepadStick::Left),
+            Vector2f::ZERO
+        );
+        assert_eq!(input.get_gamepad_trigger(id, GamepadTrigger::Right), 1.);
+
+        input.set_stick_deadzone(0.05);
+        let v = input.get_gamepad_stick(id, GamepadStick::Left);
+        assert!(v.x > 0. && v.x < 0.1);
+    }
+
+    #[test]
+    fn input_map_bindings() {
+        let (mut input, backend) = input();
+        input.set_input_map(
+            InputMap::new()
+                .with_action("jump", &[Binding::GamepadButton(GamepadButton::South)])
+                .with_axis("move_x", &[], &[])
+                .with_analog_axis("move_x", GamepadAxis::LeftStickX),
+        );
+        let id = GamepadId(3);
+        backend.send(GamepadEvent::ButtonPressed(id, GamepadButton::South));
+        backend.send(GamepadEvent::StickMoved(
+            id,
+            GamepadStick::Left,
+            Vector2f::new(-1., 0.),
+        ));
+        input.poll_gamepads();
+        assert!(input.is_action_pressed("jump"));
+        assert_eq!(input.get_axis("move_x"), -1.);
+    }
+}
@@ -0,0 +1,365 @@
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use rogalik_math::vectors::Vector2f;
+
+use super::TouchPhase;
+
+/// Thresholds of the gesture recognition.
+/// Distances are in physical pixels, durations in seconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct GestureConfig {
+    /// Max duration of a touch to count as a tap.
+    pub tap_max_duration: f32,
+    /// Max distance a finger can move during a tap (or a long press).
+    pub tap_max_distance: f32,
+    /// Max time between two taps of a double tap.
+    pub double_tap_interval: f32,
+    pub long_press_duration: f32,
+    pub swipe_min_distance: f32,
+    /// In pixels per second.
+    pub swipe_min_velocity: f32,
+}
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            tap_max_duration: 0.25,
+            tap_max_distance: 16.,
+            double_tap_interval: 0.3,
+            long_press_duration: 0.5,
+            swipe_min_distance: 64.,
+            swipe_min_velocity: 400.,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum SwipeDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Gesture recognized in the current frame.
+/// Positions are in physical pixels, as the touch positions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum Gesture {
+    Tap {
+        position: Vector2f,
+    },
+    /// Reported together with the second `Tap`.
+    DoubleTap {
+        position: Vector2f,
+    },
+    LongPress {
+        position: Vector2f,
+    },
+    Swipe {
+        start: Vector2f,
+        end: Vector2f,
+        direction: SwipeDirection,
+        /// In pixels per second.
+        velocity: Vector2f,
+    },
+    /// Two-finger pinch. `scale` is the change of the distance between the
+    /// fingers in this frame (> 1. when spreading them).
+    Pinch {
+        center: Vector2f,
+        scale: f32,
+    },
+    /// Two-finger drag, `delta` is the movement of the fingers' center in this
+    /// frame.
+    Pan {
+        delta: Vector2f,
+    },
+}
+
+struct TrackedTouch {
+    start: Vector2f,
+    start_time: f32,
+    position: Vector2f,
+    // movement exceeded the tap distance
+    moved: bool,
+    long_pressed: bool,
+    // part of a two-finger gesture
+    multi: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct GestureRecognizer {
+    pub config: GestureConfig,
+    pub gestures: Vec<Gesture>,
+    // input clock time of the last event / update
+    time: f32,
+    touches: BTreeMap<u64, TrackedTouch>,
+    last_tap: Option<(f32, Vector2f)>,
+}
+impl GestureRecognizer {
+    pub fn clear(&mut self) {
+        self.gestures.clear();
+    }
+    pub fn update(&mut self, time: f32) {
+        self.time = time;
+        for touch in self.touches.values_mut() {
+            if touch.moved || touch.multi || touch.long_pressed {
+                continue;
+            }
+            if self.time - touch.start_time >= self.config.long_press_duration {
+                touch.long_pressed = true;
+                self.gestures.push(Gesture::LongPress {
+                    position: touch.position,
+                });
+            }
+        }
+    }
+    pub fn handle_touch(&mut self, id: u64, phase: TouchPhase, position: Vector2f, time: f32) {
+        self.time = time;
+        match phase {
+            TouchPhase::Started => self.touch_started(id, position),
+            TouchPhase::Moved => self.touch_moved(id, position),
+            TouchPhase::Ended => self.touch_ended(id, position),
+            TouchPhase::Cancelled => {
+                self.touches.remove(&id);
+            }
+        }
+    }
+    fn touch_started(&mut self, id: u64, position: Vector2f) {
+        let multi = !self.touches.is_empty();
+        if multi {
+            // a second finger turns the touches into a two-finger gesture
+            for touch in self.touches.values_mut() {
+                touch.multi = true;
+            }
+        }
+        self.touches.insert(
+            id,
+            TrackedTouch {
+                start: position,
+                start_time: self.time,
+                position,
+                moved: false,
+                long_pressed: false,
+                multi,
+            },
+        );
+    }
+    fn touch_moved(&mut self, id: u64, position: Vector2f) {
+        let before = self.get_pair();
+        let Some(touch) = self.touches.get_mut(&id) else {
+            return;
+        };
+        touch.position = position;
+        if (position - touch.start).len() > self.config.tap_max_distance {
+            touch.moved = true;
+        }
+
+        let (Some((a0, b0)), Some((a1, b1))) = (before, self.get_pair()) else {
+            return;
+        };
+        let (d0, d1) = ((b0 - a0).len(), (b1 - a1).len());
+        if d0 > 0. && d0 != d1 {
+            self.add_pinch(0.5 * (a1 + b1), d1 / d0);
+        }
+        let delta = 0.5 * (a1 + b1) - 0.5 * (a0 + b0);
+        if delta != Vector2f::ZERO {
+            self.add_pan(delta);
+        }
+    }
+    fn touch_ended(&mut self, id: u64, position: Vector2f) {
+        let Some(touch) = self.touches.remove(&id) else {
+            return;
+        };
+        if touch.multi || touch.long_pressed {
+            return;
+        }
+        let duration = self.time - touch.start_time;
+        let offset = position - touch.start;
+
+        if !touch.moved && duration <= self.config.tap_max_duration {
+            self.gestures.push(Gesture::Tap { position });
+            match self.last_tap {
+                Some((time, last))
+                    if self.time - time <= self.config.double_tap_interval
+                        && (position - last).len() <= self.config.tap_max_distance =>
+                {
+                    self.gestures.push(Gesture::DoubleTap { position });
+                    self.last_tap = None;
+                }
+                _ => self.last_tap = Some((self.time, position)),
+            }
+            return;
+        }
+
+        if offset.len() < self.config.swipe_min_distance {
+            return;
+        }
+        // touches ending in the same frame they've started are treated
+        // as a single frame long
+        let velocity = offset / duration.max(1. / 60.);
+        if velocity.len() < self.config.swipe_min_velocity {
+            return;
+        }
+        let direction = if offset.x.abs() > offset.y.abs() {
+            if offset.x > 0. {
+                SwipeDirection::Right
+            } else {
+                SwipeDirection::Left
+            }
+        } else if offset.y > 0. {
+            SwipeDirection::Up
+        } else {
+            SwipeDirection::Down
+        };
+        self.gestures.push(Gesture::Swipe {
+            start: touch.start,
+            end: position,
+            direction,
+            velocity,
+        });
+    }
+    // positions of the first two fingers
+    fn get_pair(&self) -> Option<(Vector2f, Vector2f)> {
+        let mut touches = self.touches.values();
+        Some((touches.next()?.position, touches.next()?.position))
+    }
+    // multiple move events are merged into a single per frame gesture
+    fn add_pinch(&mut self, center: Vector2f, scale: f32) {
+        for gesture in self.gestures.iter_mut() {
+            if let Gesture::Pinch {
+                center: c,
+                scale: s,
+            } = gesture
+            {
+                *c = center;
+                *s *= scale;
+                return;
+            }
+        }
+        self.gestures.push(Gesture::Pinch { center, scale });
+    }
+    fn add_pan(&mut self, delta: Vector2f) {
+        for gesture in self.gestures.iter_mut() {
+            if let Gesture::Pan { delta: d } = gesture {
+                *d += delta;
+                return;
+            }
+        }
+        self.gestures.push(Gesture::Pan { delta });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::InputContext;
+
+    fn touch(input: &mut InputContext, id: u64, phase: TouchPhase, x: f32, y: f32) {
+        input.handle_event(crate::input::InputEvent::Touch {
+            id,
+            phase,
+            position: Vector2f::new(x, y),
+        });
+    }
+
+    #[test]
+    fn tap_and_double_tap() {
+        let mut input = InputContext::new();
+        touch(&mut input, 0, TouchPhase::Started, 10., 10.);
+        input.update(0.1);
+        touch(&mut input, 0, TouchPhase::Ended, 12., 10.);
+        assert_eq!(
+            input.get_gestures(),
+            [Gesture::Tap {
+                position: Vector2f::new(12., 10.)
+            }]
+        );
+        input.clear();
+
+        input.update(0.1);
+        touch(&mut input, 1, TouchPhase::Started, 10., 10.);
+        touch(&mut input, 1, TouchPhase::Ended, 10., 10.);
+        assert!(matches!(
+            input.get_gestures(),
+            [Gesture::Tap { .. }, Gesture::DoubleTap { .. }]
+        ));
+    }
+
+    #[test]
+    fn long_press() {
+        let mut input = InputContext::new();
+        touch(&mut input, 0, TouchPhase::Started, 10., 10.);
+        input.update(0.3);
+        assert!(input.get_gestures().is_empty());
+        input.update(0.3);
+        assert!(matches!(input.get_gestures(), [Gesture::LongPress { .. }]));
+        input.clear();
+
+        // reported once and not followed by a tap
+        input.update(0.3);
+        touch(&mut input, 0, TouchPhase::Ended, 10., 10.);
+        assert!(input.get_gestures().is_empty());
+    }
+
+    #[test]
+    fn swipe() {
+        let mut input = InputContext::new();
+        touch(&mut input, 0, TouchPhase::Started, 100., 100.);
+        input.update(0.1);
+        touch(&mut input, 0, TouchPhase::Moved, 100., 50.);
+        touch(&mut input, 0, TouchPhase::Ended, 100., 0.);
+        let [Gesture::Swipe {
+            direction,
+            velocity,
+            ..
+        }] = input.get_gestures()
+        else {
+            panic!("No swipe!");
+        };
+        assert_eq!(*direction, SwipeDirection::Down);
+        assert_eq!(*velocity, Vector2f::new(0., -1000.));
+
+        // too slow
+        input.clear();
+        touch(&mut input, 0, TouchPhase::Started, 100., 100.);
+        touch(&mut input, 0, TouchPhase::Moved, 150., 100.);
+        input.update(1.);
+        touch(&mut input, 0, TouchPhase::Ended, 200., 100.);
+        assert!(input.get_gestures().is_empty());
+    }
+
+    #[test]
+    fn pinch_and_pan() {
+        let mut input = InputContext::new();
+        touch(&mut input, 0, TouchPhase::Started, 0., 0.);
+        touch(&mut input, 1, TouchPhase::Started, 100., 0.);
+        touch(&mut input, 1, TouchPhase::Moved, 200., 0.);
+        touch(&mut input, 0, TouchPhase::Moved, -100., 0.);
+        let gestures = input.get_gestures();
+        assert!(gestures.contains(&Gesture::Pinch {
+            center: Vector2f::new(50., 0.),
+            scale: 3.
+        }));
+        assert!(gestures.contains(&Gesture::Pan {
+            delta: Vector2f::ZERO
+        }));
+        input.clear();
+
+        touch(&mut input, 0, TouchPhase::Moved, -100., 10.);
+        touch(&mut input, 1, TouchPhase::Moved, 200., 10.);
+        assert!(input.get_gestures().contains(&Gesture::Pan {
+            delta: Vector2f::new(0., 10.)
+        }));
+        input.clear();
+
+        // no taps when the fingers are lifted
+        touch(&mut input, 0, TouchPhase::Ended, -100., 10.);
+        touch(&mut input, 1, TouchPhase::Ended, 200., 10.);
+        assert!(input.get_gestures().is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+use gilrs::{Axis, Button, EventType, Gilrs};
+
+use rogalik_math::vectors::Vector2f;
+
+use super::gamepad::{
+    GamepadBackend, GamepadButton, GamepadEvent, GamepadId, GamepadStick, GamepadTrigger,
+};
+
+/// Gamepad backend based on `gilrs` (enabled with the `gilrs` feature).
+/// Installed by the engine builder by default.
+pub struct GilrsBackend {
+    gilrs: Gilrs,
+    // the already connected devices are reported on the first poll
+    initialized: bool,
+}
+impl GilrsBackend {
+    pub fn new() -> Option<Self> {
+        match Gilrs::new() {
+            Ok(gilrs) => Some(Self {
+                gilrs,
+                initialized: false,
+            }),
+            Err(e) => {
+                log::error!("Can't initialize the gamepad input: {}", e);
+                None
+            }
+        }
+    }
+    fn get_stick(&self, id: gilrs::GamepadId, stick: GamepadStick) -> GamepadEvent {
+        let gamepad = self.gilrs.gamepad(id);
+        let (x, y) = match stick {
+            GamepadStick::Left => (Axis::LeftStickX, Axis::LeftStickY),
+            GamepadStick::Right => (Axis::RightStickX, Axis::RightStickY),
+        };
+        GamepadEvent::StickMoved(
+            GamepadId(id.into()),
+            stick,
+            Vector2f::new(gamepad.value(x), gamepad.value(y)),
+        )
+    }
+}
+impl GamepadBackend for GilrsBackend {
+    fn poll(&mut self) -> Vec<GamepadEvent> {
+        let mut events = Vec::new();
+        if !self.initialized {
+            self.initialized = true;
+            events.extend(
+                self.gilrs
+                    .gamepads()
+                    .map(|(id, _)| GamepadEvent::Connected(GamepadId(id.into()))),
+            );
+        }
+        while let Some(gilrs::Event { id, event, .. }) = self.gilrs.next_event() {
+            let gamepad_id = GamepadId(id.into());
+            let event = match event {
+                EventType::Connected => Some(GamepadEvent::Connected(gamepad_id)),
+                EventType::Disconnected => Some(GamepadEvent::Disconnected(gamepad_id)),
+                EventType::ButtonPressed(button, _) => {
+                    get_button(button).map(|b| GamepadEvent::ButtonPressed(gamepad_id, b))
+                }
+                EventType::ButtonReleased(button, _) => {
+                    get_button(button).map(|b| GamepadEvent::ButtonReleased(gamepad_id, b))
+                }
+                EventType::ButtonChanged(button, value, _) => {
+                    get_trigger(button).map(|t| GamepadEvent::TriggerMoved(gamepad_id, t, value))
+                }
+                EventType::AxisChanged(axis, _, _) => match axis {
+                    Axis::LeftStickX | Axis::LeftStickY => {
+                        Some(self.get_stick(id, GamepadStick::Left))
+                    }
+                    Axis::RightStickX | Axis::RightStickY => {
+                        Some(self.get_stick(id, GamepadStick::Right))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+            events.extend(event);
+        }
+        events
+    }
+}
+
+fn get_button(button: Button) -> Option<GamepadButton> {
+    Some(match button {
+        Button::South => GamepadButton::South,
+        Button::East => GamepadButton::East,
+        Button::North => GamepadButton::North,
+        Button::West => GamepadButton::West,
+        Button::LeftTrigger => GamepadButton::LeftBumper,
+        Button::RightTrigger => GamepadButton::RightBumper,
+        Button::LeftThumb => GamepadButton::LeftStick,
+        Button::RightThumb => GamepadButton::RightStick,
+        Button::Select => GamepadButton::Select,
+        Button::Start => GamepadButton::Start,
+        Button::Mode => GamepadButton::Mode,
+        Button::DPadUp => GamepadButton::DPadUp,
+        Button::DPadDown => GamepadButton::DPadDown,
+        Button::DPadLeft => GamepadButton::DPadLeft,
+        Button::DPadRight => GamepadButton::DPadRight,
+        _ => return None,
+    })
+}
+
+// analog triggers
+fn get_trigger(button: Button) -> Option<GamepadTrigger> {
+    match button {
+        Button::LeftTrigger2 => Some(GamepadTrigger::Left),
+        Button::RightTrigger2 => Some(GamepadTrigger::Right),
+        _ => None,
+    }
+}
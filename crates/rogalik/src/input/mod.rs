@@ -0,0 +1,871 @@
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{ElementState, Ime, KeyEvent, Modifiers, MouseScrollDelta},
+    keyboard::PhysicalKey,
+};
+
+use rogalik_common::Camera;
+use rogalik_math::vectors::Vector2f;
+pub use winit::{
+    event::{MouseButton, TouchPhase},
+    keyboard::{KeyCode, ModifiersState},
+};
+
+mod actions;
+mod gamepad;
+mod gestures;
+#[cfg(feature = "gilrs")]
+mod gilrs;
+mod replay;
+mod text;
+mod virtual_controls;
+pub use actions::{Axis, Binding, InputMap};
+use gamepad::GamepadState;
+pub use gamepad::{
+    GamepadAxis, GamepadBackend, GamepadButton, GamepadEvent, GamepadId, GamepadStick,
+    GamepadTrigger, DEFAULT_STICK_DEADZONE, DEFAULT_TRIGGER_DEADZONE,
+};
+use gestures::GestureRecognizer;
+pub use gestures::{Gesture, GestureConfig, SwipeDirection};
+#[cfg(feature = "gilrs")]
+pub use gilrs::GilrsBackend;
+pub use replay::{InputEvent, InputRecording, RecordedFrame};
+use replay::{Recorder, Replayer};
+use text::TextInput;
+pub use virtual_controls::{VirtualButton, VirtualControls, VirtualJoystick, VIRTUAL_GAMEPAD_ID};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Touch {
+    pub position: Vector2f,
+    /// `Started`, `Ended` and `Cancelled` are reported only in the frame
+    /// of the event. Afterwards an active touch is `Moved`
+    /// (even when held still).
+    pub phase: TouchPhase,
+    /// True in the frame of the touch start - also when the touch has
+    /// already ended in the same frame (eg. a quick tap at a low frame rate).
+    pub started: bool,
+    pub start_position: Vector2f,
+    /// Input clock time of the touch start (see `InputContext::get_time`).
+    pub start_time: f32,
+    /// Movement in the current frame.
+    pub delta: Vector2f,
+}
+
+pub struct InputContext {
+    keys_down: HashSet<KeyCode>,
+    keys_pressed: HashSet<KeyCode>,
+    keys_released: HashSet<KeyCode>,
+    // press time (of the input clock)
+    mouse_buttons_down: HashMap<MouseButton, f32>,
+    mouse_buttons_pressed: HashSet<MouseButton>,
+    mouse_buttons_released: HashSet<MouseButton>,
+    mouse_physical_position: Vector2f,
+    // mouse_logical_position: Vector2f,
+    mouse_wheel_lines: Vector2f,
+    mouse_wheel_pixels: Vector2f,
+    modifiers: ModifiersState,
+    focused: bool,
+    cursor_inside: bool,
+    touches: HashMap<u64, Touch>,
+    touch_click: bool,
+    // advanced by the frame deltas, so it is reproduced in a replay
+    time: f32,
+    gestures: GestureRecognizer,
+    input_map: InputMap,
+    gamepad_backend: Option<Box<dyn GamepadBackend>>,
+    gamepads: BTreeMap<GamepadId, GamepadState>,
+    gamepads_connected: Vec<GamepadId>,
+    stick_deadzone: f32,
+    trigger_deadzone: f32,
+    text_input: TextInput,
+    recorder: Option<Recorder>,
+    replayer: Option<Replayer>,
+}
+impl Default for InputContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputContext {
+    pub fn new() -> Self {
+        Self {
+            keys_down: HashSet::new(),
+            keys_pressed: HashSet::new(),
+            keys_released: HashSet::new(),
+            mouse_buttons_down: HashMap::new(),
+            mouse_buttons_pressed: HashSet::new(),
+            mouse_buttons_released: HashSet::new(),
+            mouse_physical_position: Vector2f::ZERO,
+            mouse_wheel_lines: Vector2f::ZERO,
+            mouse_wheel_pixels: Vector2f::ZERO,
+            modifiers: ModifiersState::empty(),
+            focused: true,
+            cursor_inside: true,
+            touches: HashMap::new(),
+            touch_click: true,
+            time: 0.,
+            gestures: GestureRecognizer::default(),
+            input_map: InputMap::default(),
+            gamepad_backend: None,
+            gamepads: BTreeMap::new(),
+            gamepads_connected: Vec::new(),
+            stick_deadzone: DEFAULT_STICK_DEADZONE,
+            trigger_deadzone: DEFAULT_TRIGGER_DEADZONE,
+            text_input: TextInput::default(),
+            recorder: None,
+            replayer: None,
+        }
+    }
+    pub fn clear(&mut self) {
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+        self.mouse_buttons_pressed.clear();
+        self.mouse_buttons_released.clear();
+        self.mouse_wheel_lines = Vector2f::ZERO;
+        self.mouse_wheel_pixels = Vector2f::ZERO;
+        self.touches
+            .retain(|_, t| !matches!(t.phase, TouchPhase::Ended | TouchPhase::Cancelled));
+        for touch in self.touches.values_mut() {
+            touch.phase = TouchPhase::Moved;
+            touch.started = false;
+            touch.delta = Vector2f::ZERO;
+        }
+        self.gestures.clear();
+        self.text_input.clear();
+        self.gamepads.retain(|_, g| g.connected);
+        self.gamepads_connected.clear();
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.clear();
+        }
+    }
+    fn calculate_position(
+        &self,
+        position: PhysicalPosition<f64>,
+        window_size: &PhysicalSize<u32>,
+    ) -> Vector2f {
+        Vector2f::new(
+            position.x as f32,
+            window_size.height as f32 - position.y as f32,
+        )
+    }
+    pub fn handle_mouse_move(
+        &mut self,
+        position: PhysicalPosition<f64>,
+        window_size: &PhysicalSize<u32>,
+    ) {
+        self.handle_event(InputEvent::MouseMove(
+            self.calculate_position(position, window_size),
+        ));
+    }
+    pub fn handle_keyboard(&mut self, event: &KeyEvent) {
+        let code = match event.physical_key {
+            PhysicalKey::Code(code) => Some(code),
+            _ => None,
+        };
+        self.handle_event(InputEvent::Key {
+            code,
+            state: event.state,
+            text: event.text.as_ref().map(|t| t.to_string()),
+        });
+    }
+    pub fn handle_ime(&mut self, ime: &Ime) {
+        self.handle_event(InputEvent::Ime(ime.clone()));
+    }
+    pub fn handle_mouse_button(&mut self, button: &MouseButton, state: &ElementState) {
+        self.handle_event(InputEvent::MouseButton(*button, *state));
+    }
+    pub fn handle_mouse_wheel(&mut self, delta: &MouseScrollDelta) {
+        self.handle_event(InputEvent::MouseWheel(*delta));
+    }
+    pub fn handle_modifiers(&mut self, modifiers: &Modifiers) {
+        self.handle_event(InputEvent::Modifiers(modifiers.state()));
+    }
+    pub fn handle_focus(&mut self, focused: bool) {
+        self.handle_event(InputEvent::Focus(focused));
+    }
+    pub fn handle_cursor_entered(&mut self) {
+        self.handle_event(InputEvent::CursorEntered);
+    }
+    pub fn handle_cursor_left(&mut self) {
+        self.handle_event(InputEvent::CursorLeft);
+    }
+    pub fn handle_touch(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        position: PhysicalPosition<f64>,
+        window_size: &PhysicalSize<u32>,
+    ) {
+        self.handle_event(InputEvent::Touch {
+            id,
+            phase,
+            position: self.calculate_position(position, window_size),
+        });
+    }
+    pub fn handle_gamepad_event(&mut self, event: GamepadEvent) {
+        self.handle_event(InputEvent::Gamepad(event));
+    }
+    /// Records and applies the event.
+    /// Live events are ignored while a recording is being replayed.
+    pub fn handle_event(&mut self, event: InputEvent) {
+        if self.replayer.is_some() {
+            return;
+        }
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.pending.push(event.clone());
+        }
+        self.apply_event(event);
+    }
+    /// Applies an event derived from the other (already recorded) input,
+    /// eg. by the virtual controls. It is not recorded and is applied also
+    /// during a replay, as it is reproduced from the replayed events.
+    pub(crate) fn handle_derived_event(&mut self, event: InputEvent) {
+        self.apply_event(event);
+    }
+    fn apply_event(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Key { code, state, text } => self.apply_key(code, state, text),
+            InputEvent::Ime(ime) => self.text_input.handle_ime(&ime),
+            InputEvent::MouseButton(button, state) => self.apply_mouse_button(button, state),
+            InputEvent::MouseMove(position) => self.mouse_physical_position = position,
+            InputEvent::MouseWheel(delta) => self.apply_mouse_wheel(delta),
+            InputEvent::Modifiers(modifiers) => self.modifiers = modifiers,
+            InputEvent::Focus(focused) => self.apply_focus(focused),
+            InputEvent::CursorEntered => self.cursor_inside = true,
+            InputEvent::CursorLeft => self.cursor_inside = false,
+            InputEvent::Touch {
+                id,
+                phase,
+                position,
+            } => self.apply_touch(id, phase, position),
+            InputEvent::Gamepad(event) => self.apply_gamepad_event(event),
+        }
+    }
+    fn apply_key(&mut self, code: Option<KeyCode>, state: ElementState, text: Option<String>) {
+        if state == ElementState::Pressed {
+            if let Some(text) = text {
+                self.text_input.push_text(&text);
+            }
+        }
+        if let Some(code) = code {
+            match state {
+                ElementState::Pressed => {
+                    if !self.keys_down.contains(&code) {
+                        self.keys_pressed.insert(code);
+                        self.keys_down.insert(code);
+                    }
+                }
+                ElementState::Released => {
+                    self.keys_down.remove(&code);
+                    self.keys_released.insert(code);
+                }
+            };
+        }
+    }
+    fn apply_mouse_button(&mut self, button: MouseButton, state: ElementState) {
+        match state {
+            ElementState::Pressed => {
+                if let Entry::Vacant(entry) = self.mouse_buttons_down.entry(button) {
+                    entry.insert(self.time);
+                    self.mouse_buttons_pressed.insert(button);
+                }
+            }
+            ElementState::Released => {
+                self.mouse_buttons_down.remove(&button);
+                self.mouse_buttons_released.insert(button);
+            }
+        };
+    }
+    fn apply_mouse_wheel(&mut self, delta: MouseScrollDelta) {
+        match delta {
+            MouseScrollDelta::LineDelta(x, y) => {
+                self.mouse_wheel_lines += Vector2f::new(x, y);
+            }
+            MouseScrollDelta::PixelDelta(position) => {
+                self.mouse_wheel_pixels += Vector2f::new(position.x as f32, position.y as f32);
+            }
+        }
+    }
+    fn apply_focus(&mut self, focused: bool) {
+        self.focused = focused;
+        if !focused {
+            // release events are not delivered to an unfocused window,
+            // so the held inputs would get stuck
+            self.keys_released.extend(self.keys_down.drain());
+            self.mouse_buttons_released
+                .extend(self.mouse_buttons_down.drain().map(|(b, _)| b));
+            self.modifiers = ModifiersState::empty();
+        }
+    }
+    fn apply_touch(&mut self, id: u64, phase: TouchPhase, position: Vector2f) {
+        let touch = self.touches.entry(id).or_insert(Touch {
+            position,
+            phase,
+            started: false,
+            start_position: position,
+            start_time: self.time,
+            delta: Vector2f::ZERO,
+        });
+        if phase == TouchPhase::Started {
+            // ids can be reused by the OS
+            touch.started = true;
+            touch.start_position = position;
+            touch.start_time = self.time;
+            touch.delta = Vector2f::ZERO;
+        } else {
+            touch.delta += position - touch.position;
+        }
+        touch.position = position;
+        // do not let a late Moved event hide the frame's Started
+        if !(touch.phase == TouchPhase::Started && phase == TouchPhase::Moved) {
+            touch.phase = phase;
+        }
+        self.gestures.handle_touch(id, phase, position, self.time);
+
+        if self.touch_click {
+            match phase {
+                TouchPhase::Started => {
+                    self.apply_mouse_button(MouseButton::Left, ElementState::Pressed);
+                    self.mouse_physical_position = position;
+                }
+                TouchPhase::Ended => {
+                    self.apply_mouse_button(MouseButton::Left, ElementState::Released)
+                }
+                _ => (),
+            };
+        }
+    }
+    /// Starts recording the handled input events.
+    /// Any previous, unfinished recording is discarded.
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(Recorder::default());
+    }
+    /// Finishes the recording and returns the log.
+    /// Events of the frame in progress are not included.
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        self.recorder.take().map(|r| r.recording)
+    }
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+    /// Feeds the recorded events back (frame by frame) instead of the live
+    /// ones. Returns to the live input when the recording ends.
+    pub fn start_replay(&mut self, recording: InputRecording) {
+        self.replayer = Some(Replayer {
+            recording,
+            frame: 0,
+        });
+    }
+    pub fn stop_replay(&mut self) {
+        self.replayer = None;
+    }
+    pub fn is_replaying(&self) -> bool {
+        self.replayer.is_some()
+    }
+    /// Advances the time based input state (eg. long press detection)
+    /// and closes the current frame of the recording (if active).
+    /// Called by the engine once per frame, after `Time::update`.
+    /// Public, so custom (eg. headless) loops can drive the input as well.
+    pub fn update(&mut self, delta: f32) {
+        self.time += delta;
+        self.gestures.update(self.time);
+        if let Some(recorder) = self.recorder.as_mut() {
+            let events = std::mem::take(&mut recorder.pending);
+            recorder.recording.push_frame(delta, events);
+        }
+    }
+    /// Applies the events of the next recorded frame (if replaying)
+    /// and returns its duration, that should be passed to
+    /// `Time::update_with_delta`.
+    /// Called by the engine once per frame, before the time update.
+    pub fn replay_frame(&mut self) -> Option<f32> {
+        let replayer = self.replayer.as_mut()?;
+        let Some(frame) = replayer.recording.get_frames().get(replayer.frame).cloned() else {
+            self.replayer = None;
+            return None;
+        };
+        replayer.frame += 1;
+        for event in frame.events {
+            self.apply_event(event);
+        }
+        Some(frame.delta)
+    }
+    /// Starts capturing the typed characters and enables the IME
+    /// (eg. for a player name entry or a chat).
+    pub fn enable_text_input(&mut self) {
+        self.text_input.set_enabled(true);
+    }
+    pub fn disable_text_input(&mut self) {
+        self.text_input.set_enabled(false);
+    }
+    pub fn is_text_input_enabled(&self) -> bool {
+        self.text_input.enabled
+    }
+    /// Returns the characters typed (or committed by the IME) in this frame.
+    /// Control characters are skipped - backspace, enter etc. should be
+    /// checked with the key codes.
+    pub fn get_text_input(&self) -> &str {
+        &self.text_input.buffer
+    }
+    /// Returns the text currently composed by the IME (not yet committed)
+    /// and the cursor position as a byte range within it.
+    pub fn get_ime_preedit(&self) -> Option<(&str, Option<(usize, usize)>)> {
+        self.text_input
+            .preedit
+            .as_ref()
+            .map(|(text, cursor)| (text.as_str(), *cursor))
+    }
+    /// Sets the area of the edited text, so the IME candidate box
+    /// can be placed next to it.
+    /// Position (bottom-left corner) and size are in physical pixels,
+    /// as the mouse position.
+    pub fn set_ime_cursor_area(&mut self, position: Vector2f, size: Vector2f) {
+        self.text_input.cursor_area_request = Some((position, size));
+    }
+    /// Returns the pending text input mode change.
+    /// (called by the engine internally)
+    pub(crate) fn take_text_input_request(&mut self) -> Option<bool> {
+        self.text_input.enabled_request.take()
+    }
+    /// Returns the pending IME cursor area change.
+    /// (called by the engine internally)
+    pub(crate) fn take_ime_cursor_area_request(&mut self) -> Option<(Vector2f, Vector2f)> {
+        self.text_input.cursor_area_request.take()
+    }
+    pub fn set_gamepad_backend(&mut self, backend: Box<dyn GamepadBackend>) {
+        self.gamepad_backend = Some(backend);
+    }
+    /// Processes the events of the gamepad backend (if set).
+    /// (called by the engine internally, once per frame)
+    pub(crate) fn poll_gamepads(&mut self) {
+        let Some(backend) = self.gamepad_backend.as_mut() else {
+            return;
+        };
+        for event in backend.poll() {
+            self.handle_gamepad_event(event);
+        }
+    }
+    fn apply_gamepad_event(&mut self, event: GamepadEvent) {
+        match event {
+            GamepadEvent::Connected(id) => {
+                if self.is_gamepad_connected(id) {
+                    return;
+                }
+                self.gamepads.insert(id, GamepadState::new());
+                if !self.gamepads_connected.contains(&id) {
+                    self.gamepads_connected.push(id);
+                }
+            }
+            GamepadEvent::Disconnected(id) => {
+                if let Some(gamepad) = self.gamepads.get_mut(&id) {
+                    gamepad.disconnect();
+                }
+            }
+            GamepadEvent::ButtonPressed(id, button) => self.gamepad_mut(id).press(button),
+            GamepadEvent::ButtonReleased(id, button) => self.gamepad_mut(id).release(button),
+            GamepadEvent::StickMoved(id, stick, value) => {
+                self.gamepad_mut(id).sticks[gamepad::stick_index(stick)] = value
+            }
+            GamepadEvent::TriggerMoved(id, trigger, value) => {
+                self.gamepad_mut(id).triggers[gamepad::trigger_index(trigger)] = value
+            }
+        }
+    }
+    // Some backends do not report the already connected devices,
+    // so they are registered on their first event.
+    fn gamepad_mut(&mut self, id: GamepadId) -> &mut GamepadState {
+        self.gamepads.entry(id).or_insert_with(|| {
+            self.gamepads_connected.push(id);
+            GamepadState::new()
+        })
+    }
+    pub fn set_stick_deadzone(&mut self, value: f32) {
+        self.stick_deadzone = value.clamp(0., 0.99);
+    }
+    pub fn set_trigger_deadzone(&mut self, value: f32) {
+        self.trigger_deadzone = value.clamp(0., 0.99);
+    }
+    /// Returns the currently connected gamepads.
+    pub fn get_gamepads(&self) -> impl Iterator<Item = GamepadId> + '_ {
+        self.gamepads
+            .iter()
+            .filter(|(_, g)| g.connected)
+            .map(|(id, _)| *id)
+    }
+    pub fn is_gamepad_connected(&self, id: GamepadId) -> bool {
+        self.gamepads.get(&id).is_some_and(|g| g.connected)
+    }
+    /// Returns the gamepads connected in this frame.
+    pub fn get_gamepads_connected(&self) -> &[GamepadId] {
+        &self.gamepads_connected
+    }
+    /// Returns the gamepads disconnected in this frame.
+    pub fn get_gamepads_disconnected(&self) -> impl Iterator<Item = GamepadId> + '_ {
+        self.gamepads
+            .iter()
+            .filter(|(_, g)| !g.connected)
+            .map(|(id, _)| *id)
+    }
+    pub fn is_gamepad_button_down(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&id)
+            .is_some_and(|g| g.buttons_down.contains(&button))
+    }
+    pub fn is_gamepad_button_pressed(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&id)
+            .is_some_and(|g| g.buttons_pressed.contains(&button))
+    }
+    pub fn is_gamepad_button_released(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&id)
+            .is_some_and(|g| g.buttons_released.contains(&button))
+    }
+    /// Returns the stick position with the deadzone applied.
+    pub fn get_gamepad_stick(&self, id: GamepadId, stick: GamepadStick) -> Vector2f {
+        self.gamepads.get(&id).map_or(Vector2f::ZERO, |g| {
+            gamepad::apply_stick_deadzone(
+                g.sticks[gamepad::stick_index(stick)],
+                self.stick_deadzone,
+            )
+        })
+    }
+    /// Returns the trigger value with the deadzone applied.
+    pub fn get_gamepad_trigger(&self, id: GamepadId, trigger: GamepadTrigger) -> f32 {
+        self.gamepads.get(&id).map_or(0., |g| {
+            gamepad::apply_trigger_deadzone(
+                g.triggers[gamepad::trigger_index(trigger)],
+                self.trigger_deadzone,
+            )
+        })
+    }
+    /// Returns the value of the gamepad axis with the deadzone applied.
+    pub fn get_gamepad_axis(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        match axis {
+            GamepadAxis::LeftStickX => self.get_gamepad_stick(id, GamepadStick::Left).x,
+            GamepadAxis::LeftStickY => self.get_gamepad_stick(id, GamepadStick::Left).y,
+            GamepadAxis::RightStickX => self.get_gamepad_stick(id, GamepadStick::Right).x,
+            GamepadAxis::RightStickY => self.get_gamepad_stick(id, GamepadStick::Right).y,
+            GamepadAxis::LeftTrigger => self.get_gamepad_trigger(id, GamepadTrigger::Left),
+            GamepadAxis::RightTrigger => self.get_gamepad_trigger(id, GamepadTrigger::Right),
+        }
+    }
+    pub fn get_mouse_physical_position(&self) -> Vector2f {
+        self.mouse_physical_position
+    }
+    // pub fn get_mouse_logical_position(&self) -> Vector2f {
+    //     self.mouse_logical_position
+    // }
+    /// Returns the mouse position converted to the world coordinates
+    /// of the given camera.
+    pub fn get_mouse_world_position(&self, camera: &dyn Camera) -> Vector2f {
+        camera.camera_to_world(self.mouse_physical_position)
+    }
+    /// Returns the wheel scroll of this frame, in lines (as reported by
+    /// most mouse wheels). Positive y means scrolling up (away from the user).
+    pub fn get_mouse_wheel_lines(&self) -> Vector2f {
+        self.mouse_wheel_lines
+    }
+    /// Returns the wheel scroll of this frame, in pixels (as reported by
+    /// touchpads and other precise devices).
+    pub fn get_mouse_wheel_pixels(&self) -> Vector2f {
+        self.mouse_wheel_pixels
+    }
+    pub fn get_modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+    pub fn is_shift_down(&self) -> bool {
+        self.modifiers.shift_key()
+    }
+    pub fn is_control_down(&self) -> bool {
+        self.modifiers.control_key()
+    }
+    pub fn is_alt_down(&self) -> bool {
+        self.modifiers.alt_key()
+    }
+    /// Windows / Command key.
+    pub fn is_super_down(&self) -> bool {
+        self.modifiers.super_key()
+    }
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+    /// False when the mouse cursor is outside of the window.
+    pub fn is_cursor_inside(&self) -> bool {
+        self.cursor_inside
+    }
+    pub fn is_key_down(&self, code: KeyCode) -> bool {
+        self.keys_down.contains(&code)
+    }
+    pub fn is_key_pressed(&self, code: KeyCode) -> bool {
+        self.keys_pressed.contains(&code)
+    }
+    pub fn is_key_released(&self, code: KeyCode) -> bool {
+        self.keys_released.contains(&code)
+    }
+    pub fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_down.contains_key(&button)
+    }
+    pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_pressed.contains(&button)
+    }
+    pub fn is_mouse_button_released(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_released.contains(&button)
+    }
+    pub fn is_mouse_button_held(&self, button: MouseButton, thresh_secs: f32) -> bool {
+        self.mouse_buttons_down
+            .get(&button)
+            .map(|start| self.time - start > thresh_secs)
+            .unwrap_or(false)
+    }
+    /// Returns the active touches, and the ones ended in this frame.
+    pub fn get_touches(&self) -> &HashMap<u64, Touch> {
+        &self.touches
+    }
+    /// Returns the time (in seconds) of the input clock.
+    /// It is advanced by the frame durations, so also in a replay.
+    pub fn get_time(&self) -> f32 {
+        self.time
+    }
+    /// Returns the touch gestures recognized in this frame.
+    pub fn get_gestures(&self) -> &[Gesture] {
+        &self.gestures.gestures
+    }
+    pub fn get_gesture_config(&self) -> &GestureConfig {
+        &self.gestures.config
+    }
+    pub fn set_gesture_config(&mut self, config: GestureConfig) {
+        self.gestures.config = config;
+    }
+    /// When enabled (default) touches emulate the left mouse button.
+    pub fn set_touch_click(&mut self, value: bool) {
+        self.touch_click = value;
+    }
+    pub fn is_touch_click(&self) -> bool {
+        self.touch_click
+    }
+    pub fn get_input_map(&self) -> &InputMap {
+        &self.input_map
+    }
+    pub fn get_input_map_mut(&mut self) -> &mut InputMap {
+        &mut self.input_map
+    }
+    pub fn set_input_map(&mut self, input_map: InputMap) {
+        self.input_map = input_map;
+    }
+    /// True if any of the action's bindings is down.
+    pub fn is_action_down(&self, action: &str) -> bool {
+        self.input_map
+            .get_action_bindings(action)
+            .iter()
+            .any(|b| self.is_binding_down(*b))
+    }
+    /// True if any of the action's bindings has been pressed in this frame.
+    pub fn is_action_pressed(&self, action: &str) -> bool {
+        self.input_map
+            .get_action_bindings(action)
+            .iter()
+            .any(|b| self.is_binding_pressed(*b))
+    }
+    /// True if any of the action's bindings has been released in this frame.
+    pub fn is_action_released(&self, action: &str) -> bool {
+        self.input_map
+            .get_action_bindings(action)
+            .iter()
+            .any(|b| self.is_binding_released(*b))
+    }
+    /// Returns the axis value in the -1. to 1. range.
+    /// Digital bindings take precedence over the analog ones,
+    /// from the analog values (of all the gamepads) the largest one is used.
+    /// Unknown axes evaluate to 0.
+    pub fn get_axis(&self, axis: &str) -> f32 {
+        let Some(axis) = self.input_map.get_axis(axis) else {
+            return 0.;
+        };
+        let mut value = 0.;
+        if axis.negative.iter().any(|b| self.is_binding_down(*b)) {
+            value -= 1.;
+        }
+        if axis.positive.iter().any(|b| self.is_binding_down(*b)) {
+            value += 1.;
+        }
+        if value != 0. {
+            return value;
+        }
+        self.get_gamepads()
+            .flat_map(|id| {
+                axis.analog
+                    .iter()
+                    .map(move |a| self.get_gamepad_axis(id, *a))
+            })
+            .fold(0., |acc, v| if v.abs() > acc.abs() { v } else { acc })
+    }
+    /// Combines two axes into a vector, with the length clamped to 1.
+    pub fn get_axis_vector(&self, x_axis: &str, y_axis: &str) -> Vector2f {
+        let v = Vector2f::new(self.get_axis(x_axis), self.get_axis(y_axis));
+        if v.len_sq() > 1. {
+            return v.normalized();
+        }
+        v
+    }
+    pub fn is_binding_down(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::GamepadButton(button) => self
+                .gamepads
+                .keys()
+                .any(|id| self.is_gamepad_button_down(*id, button)),
+            Binding::Key(code) => self.is_key_down(code),
+            Binding::Mouse(button) => self.is_mouse_button_down(button),
+            Binding::Touch => self
+                .touches
+                .values()
+                .any(|t| matches!(t.phase, TouchPhase::Started | TouchPhase::Moved)),
+        }
+    }
+    pub fn is_binding_pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::GamepadButton(button) => self
+                .gamepads
+                .keys()
+                .any(|id| self.is_gamepad_button_pressed(*id, button)),
+            Binding::Key(code) => self.is_key_pressed(code),
+            Binding::Mouse(button) => self.is_mouse_button_pressed(button),
+            Binding::Touch => self.touches.values().any(|t| t.started),
+        }
+    }
+    pub fn is_binding_released(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::GamepadButton(button) => self
+                .gamepads
+                .keys()
+                .any(|id| self.is_gamepad_button_released(*id, button)),
+            Binding::Key(code) => self.is_key_released(code),
+            Binding::Mouse(button) => self.is_mouse_button_released(button),
+            Binding::Touch => self
+                .touches
+                .values()
+                .any(|t| matches!(t.phase, TouchPhase::Ended | TouchPhase::Cancelled)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestCamera;
+    impl Camera for TestCamera {
+        fn get_target(&self) -> Vector2f {
+            Vector2f::ZERO
+        }
+        fn get_scale(&self) -> f32 {
+            2.
+        }
+        fn set_target(&mut self, _target: Vector2f) {}
+        fn set_scale(&mut self, _scale: f32) {}
+        fn camera_to_world(&self, v: Vector2f) -> Vector2f {
+            v / self.get_scale()
+        }
+        fn get_bounds(&self) -> (Vector2f, Vector2f) {
+            (Vector2f::ZERO, Vector2f::ZERO)
+        }
+    }
+
+    #[test]
+    fn mouse_wheel() {
+        let mut input = InputContext::new();
+        input.handle_mouse_wheel(&MouseScrollDelta::LineDelta(0., 1.));
+        input.handle_mouse_wheel(&MouseScrollDelta::LineDelta(0., 2.));
+        input.handle_mouse_wheel(&MouseScrollDelta::PixelDelta(PhysicalPosition::new(
+            5., -3.,
+        )));
+        assert_eq!(input.get_mouse_wheel_lines(), Vector2f::new(0., 3.));
+        assert_eq!(input.get_mouse_wheel_pixels(), Vector2f::new(5., -3.));
+
+        input.clear();
+        assert_eq!(input.get_mouse_wheel_lines(), Vector2f::ZERO);
+        assert_eq!(input.get_mouse_wheel_pixels(), Vector2f::ZERO);
+    }
+
+    #[test]
+    fn mouse_button_held() {
+        let mut input = InputContext::new();
+        input.handle_mouse_button(&MouseButton::Left, &ElementState::Pressed);
+        input.update(0.3);
+        assert!(!input.is_mouse_button_held(MouseButton::Left, 0.5));
+        input.update(0.3);
+        assert!(input.is_mouse_button_held(MouseButton::Left, 0.5));
+    }
+
+    #[test]
+    fn focus_lost_releases_inputs() {
+        let mut input = InputContext::new();
+        input.keys_down.insert(KeyCode::KeyW);
+        input.handle_mouse_button(&MouseButton::Left, &ElementState::Pressed);
+        input.modifiers = ModifiersState::SHIFT;
+        input.clear();
+
+        input.handle_focus(false);
+        assert!(!input.is_focused());
+        assert!(!input.is_key_down(KeyCode::KeyW));
+        assert!(input.is_key_released(KeyCode::KeyW));
+        assert!(input.is_mouse_button_released(MouseButton::Left));
+        assert!(!input.is_shift_down());
+    }
+
+    #[test]
+    fn touches_persist_until_ended() {
+        let mut input = InputContext::new();
+        let size = PhysicalSize::new(100, 100);
+        input.update(1.);
+        input.handle_touch(
+            0,
+            TouchPhase::Started,
+            PhysicalPosition::new(10., 90.),
+            &size,
+        );
+        input.handle_touch(0, TouchPhase::Moved, PhysicalPosition::new(15., 90.), &size);
+        let touch = input.get_touches()[&0];
+        assert_eq!(touch.phase, TouchPhase::Started);
+        assert_eq!(touch.delta, Vector2f::new(5., 0.));
+        assert_eq!(touch.start_time, 1.);
+        assert!(input.is_binding_pressed(Binding::Touch));
+        input.clear();
+
+        // held still
+        input.update(1.);
+        let touch = input.get_touches()[&0];
+        assert_eq!(touch.phase, TouchPhase::Moved);
+        assert_eq!(touch.delta, Vector2f::ZERO);
+        assert_eq!(touch.start_position, Vector2f::new(10., 10.));
+        assert!(input.is_binding_down(Binding::Touch));
+        assert!(!input.is_binding_pressed(Binding::Touch));
+        input.clear();
+
+        input.handle_touch(0, TouchPhase::Ended, PhysicalPosition::new(20., 90.), &size);
+        assert_eq!(input.get_touches()[&0].phase, TouchPhase::Ended);
+        assert!(input.is_binding_released(Binding::Touch));
+        input.clear();
+        assert!(input.get_touches().is_empty());
+    }
+
+    #[test]
+    fn mouse_world_position() {
+        let mut input = InputContext::new();
+        input.handle_mouse_move(
+            PhysicalPosition::new(10., 20.),
+            &PhysicalSize::new(100, 100),
+        );
+        assert_eq!(
+            input.get_mouse_world_position(&TestCamera),
+            Vector2f::new(5., 40.)
+        );
+        // the default inverse
+        assert_eq!(
+            TestCamera.world_to_camera(Vector2f::new(5., 40.)),
+            Vector2f::new(10., 80.)
+        );
+    }
+}
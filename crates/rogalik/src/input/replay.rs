@@ -0,0 +1,170 @@
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use winit::event::{ElementState, Ime, MouseScrollDelta};
+
+use rogalik_math::vectors::Vector2f;
+
+use super::{GamepadEvent, KeyCode, ModifiersState, MouseButton, TouchPhase};
+
+/// Input event, as handled by the `InputContext`.
+/// Positions are already converted to the physical pixels
+/// (with the bottom-left origin), so the replay does not depend
+/// on the window size.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum InputEvent {
+    Key {
+        code: Option<KeyCode>,
+        state: ElementState,
+        text: Option<String>,
+    },
+    Ime(Ime),
+    MouseButton(MouseButton, ElementState),
+    MouseMove(Vector2f),
+    MouseWheel(MouseScrollDelta),
+    Modifiers(ModifiersState),
+    Focus(bool),
+    CursorEntered,
+    CursorLeft,
+    Touch {
+        id: u64,
+        phase: TouchPhase,
+        position: Vector2f,
+    },
+    Gamepad(GamepadEvent),
+}
+
+/// Events handled within a single frame.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct RecordedFrame {
+    /// Frame duration, as measured by `Time::update`.
+    pub delta: f32,
+    pub events: Vec<InputEvent>,
+}
+
+/// Log of the input events, recorded frame by frame.
+/// Replaying it (with the same random seed) reproduces the game session.
+/// With the `serialize` feature enabled it can be stored
+/// (eg. attached to a bug report) via `rogalik_persist`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct InputRecording {
+    frames: Vec<RecordedFrame>,
+}
+impl InputRecording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Frame number is the index in the slice.
+    pub fn get_frames(&self) -> &[RecordedFrame] {
+        &self.frames
+    }
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+    /// Appends a frame. Can be used to script the input for automated tests.
+    pub fn push_frame(&mut self, delta: f32, events: Vec<InputEvent>) {
+        self.frames.push(RecordedFrame { delta, events });
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Recorder {
+    pub recording: InputRecording,
+    // events of the frame in progress
+    pub pending: Vec<InputEvent>,
+}
+
+pub(crate) struct Replayer {
+    pub recording: InputRecording,
+    pub frame: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::InputContext;
+    use crate::time::Time;
+
+    // mimics the engine's frame loop
+    fn frame(input: &mut InputContext, time: &mut Time) {
+        match input.replay_frame() {
+            Some(delta) => time.update_with_delta(delta),
+            None => time.update(),
+        }
+        input.update(time.get_frame_delta());
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let mut input = InputContext::new();
+        let mut time = Time::new();
+        input.start_recording();
+
+        input.handle_mouse_button(&MouseButton::Left, &ElementState::Pressed);
+        frame(&mut input, &mut time);
+        input.clear();
+        frame(&mut input, &mut time);
+        input.clear();
+        input.handle_mouse_button(&MouseButton::Left, &ElementState::Released);
+        frame(&mut input, &mut time);
+        input.clear();
+
+        let recording = input.stop_recording().unwrap();
+        assert_eq!(recording.frame_count(), 3);
+        assert_eq!(recording.get_frames()[1].events, []);
+
+        let mut input = InputContext::new();
+        let mut time = Time::new();
+        input.start_replay(recording.clone());
+
+        frame(&mut input, &mut time);
+        assert_eq!(time.get_frame_delta(), recording.get_frames()[0].delta);
+        assert!(input.is_mouse_button_pressed(MouseButton::Left));
+        input.clear();
+
+        // live events are ignored while replaying
+        input.handle_mouse_button(&MouseButton::Left, &ElementState::Released);
+        frame(&mut input, &mut time);
+        assert!(input.is_mouse_button_down(MouseButton::Left));
+        input.clear();
+
+        frame(&mut input, &mut time);
+        assert!(input.is_mouse_button_released(MouseButton::Left));
+        assert!(input.is_replaying());
+        input.clear();
+
+        // back to the live input
+        frame(&mut input, &mut time);
+        assert!(!input.is_replaying());
+    }
+
+    #[test]
+    fn touch_click_is_not_recorded_twice() {
+        let mut input = InputContext::new();
+        input.start_recording();
+        input.handle_touch(
+            0,
+            TouchPhase::Started,
+            winit::dpi::PhysicalPosition::new(1., 1.),
+            &winit::dpi::PhysicalSize::new(10, 10),
+        );
+        input.update(0.1);
+        let recording = input.stop_recording().unwrap();
+        assert_eq!(
+            recording.get_frames()[0].events,
+            [InputEvent::Touch {
+                id: 0,
+                phase: TouchPhase::Started,
+                position: Vector2f::new(1., 9.)
+            }]
+        );
+
+        let mut input = InputContext::new();
+        input.start_replay(recording);
+        input.replay_frame();
+        assert!(input.is_mouse_button_pressed(MouseButton::Left));
+        assert_eq!(input.get_mouse_physical_position(), Vector2f::new(1., 9.));
+    }
+}
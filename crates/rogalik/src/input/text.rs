@@ -0,0 +1,96 @@
+use winit::event::Ime;
+
+use rogalik_math::vectors::Vector2f;
+
+/// Typed characters and IME composition state.
+#[derive(Default)]
+pub(crate) struct TextInput {
+    pub enabled: bool,
+    // characters typed in the current frame
+    pub buffer: String,
+    // text being composed by the IME, with an optional cursor byte range
+    pub preedit: Option<(String, Option<(usize, usize)>)>,
+    // window requests, applied by the engine
+    pub enabled_request: Option<bool>,
+    pub cursor_area_request: Option<(Vector2f, Vector2f)>,
+}
+impl TextInput {
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+    pub fn set_enabled(&mut self, value: bool) {
+        if self.enabled != value {
+            self.enabled = value;
+            self.enabled_request = Some(value);
+        }
+        if !value {
+            self.buffer.clear();
+            self.preedit = None;
+        }
+    }
+    pub fn push_text(&mut self, text: &str) {
+        if !self.enabled {
+            return;
+        }
+        // control characters (backspace, enter etc.) should be handled via the key codes
+        self.buffer.extend(text.chars().filter(|c| !c.is_control()));
+    }
+    pub fn handle_ime(&mut self, ime: &Ime) {
+        match ime {
+            Ime::Preedit(text, cursor) => {
+                self.preedit = match text.is_empty() {
+                    true => None,
+                    false => Some((text.clone(), *cursor)),
+                }
+            }
+            Ime::Commit(text) => {
+                self.preedit = None;
+                self.push_text(text);
+            }
+            Ime::Disabled => self.preedit = None,
+            Ime::Enabled => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::InputContext;
+
+    #[test]
+    fn text_only_when_enabled() {
+        let mut input = InputContext::new();
+        input.handle_ime(&Ime::Commit("a".to_string()));
+        assert_eq!(input.get_text_input(), "");
+
+        input.enable_text_input();
+        assert_eq!(input.take_text_input_request(), Some(true));
+        input.text_input.push_text("b\u{8}\r");
+        input.handle_ime(&Ime::Commit("ć".to_string()));
+        assert_eq!(input.get_text_input(), "bć");
+
+        input.clear();
+        assert_eq!(input.get_text_input(), "");
+        input.disable_text_input();
+        assert_eq!(input.take_text_input_request(), Some(false));
+        assert_eq!(input.take_text_input_request(), None);
+    }
+
+    #[test]
+    fn ime_preedit() {
+        let mut input = InputContext::new();
+        input.enable_text_input();
+        input.handle_ime(&Ime::Preedit("ni".to_string(), Some((2, 2))));
+        assert_eq!(input.get_ime_preedit(), Some(("ni", Some((2, 2)))));
+
+        // composition persists between the frames
+        input.clear();
+        assert!(input.get_ime_preedit().is_some());
+
+        input.handle_ime(&Ime::Preedit(String::new(), None));
+        input.handle_ime(&Ime::Commit("你".to_string()));
+        assert_eq!(input.get_ime_preedit(), None);
+        assert_eq!(input.get_text_input(), "你");
+    }
+}
@@ -0,0 +1,345 @@
+use rogalik_common::{Color, EngineError, GraphicsContext, SpriteParams};
+use rogalik_math::vectors::Vector2f;
+
+use super::{
+    GamepadButton, GamepadEvent, GamepadId, GamepadStick, InputContext, InputEvent, TouchPhase,
+};
+
+/// Default id of the gamepad emulated by the virtual controls.
+pub const VIRTUAL_GAMEPAD_ID: GamepadId = GamepadId(usize::MAX);
+
+/// On-screen joystick, reported as a gamepad stick.
+/// Position and radius are in physical pixels (as the touches).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VirtualJoystick {
+    pub center: Vector2f,
+    pub radius: f32,
+    pub stick: GamepadStick,
+    touch: Option<u64>,
+    value: Vector2f,
+}
+impl VirtualJoystick {
+    pub fn new(center: Vector2f, radius: f32, stick: GamepadStick) -> Self {
+        Self {
+            center,
+            radius,
+            stick,
+            touch: None,
+            value: Vector2f::ZERO,
+        }
+    }
+    /// Returns the stick position (before the deadzone is applied).
+    pub fn get_value(&self) -> Vector2f {
+        self.value
+    }
+}
+
+/// On-screen button, reported as a gamepad button.
+/// Position and radius are in physical pixels (as the touches).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VirtualButton {
+    pub center: Vector2f,
+    pub radius: f32,
+    pub button: GamepadButton,
+    touch: Option<u64>,
+}
+impl VirtualButton {
+    pub fn new(center: Vector2f, radius: f32, button: GamepadButton) -> Self {
+        Self {
+            center,
+            radius,
+            button,
+            touch: None,
+        }
+    }
+    pub fn is_down(&self) -> bool {
+        self.touch.is_some()
+    }
+}
+
+/// Set of on-screen touch controls, emulating a gamepad.
+/// As the state is fed through the regular gamepad events,
+/// input map bindings (and the `is_gamepad_*` queries) work unchanged.
+///
+/// The controls are drawn with a user material, using the atlas sprites:
+/// - `joystick_sprite` for the joystick's base
+/// - `knob_sprite` for the joystick's knob
+/// - `button_sprite` for the buttons
+///
+/// It is advisable to disable the touch click emulation
+/// (`InputContext::set_touch_click`) when using the virtual controls.
+pub struct VirtualControls {
+    pub material: String,
+    pub joystick_sprite: usize,
+    pub knob_sprite: usize,
+    pub button_sprite: usize,
+    pub color: Color,
+    pub pressed_color: Color,
+    pub joysticks: Vec<VirtualJoystick>,
+    pub buttons: Vec<VirtualButton>,
+    gamepad_id: GamepadId,
+    connected: bool,
+}
+impl VirtualControls {
+    pub fn new(material: &str) -> Self {
+        Self {
+            material: material.to_string(),
+            joystick_sprite: 0,
+            knob_sprite: 1,
+            button_sprite: 2,
+            color: Color(255, 255, 255, 128),
+            pressed_color: Color(255, 255, 255, 224),
+            joysticks: Vec::new(),
+            buttons: Vec::new(),
+            gamepad_id: VIRTUAL_GAMEPAD_ID,
+            connected: false,
+        }
+    }
+    pub fn with_joystick(mut self, center: Vector2f, radius: f32, stick: GamepadStick) -> Self {
+        self.joysticks
+            .push(VirtualJoystick::new(center, radius, stick));
+        self
+    }
+    pub fn with_button(mut self, center: Vector2f, radius: f32, button: GamepadButton) -> Self {
+        self.buttons
+            .push(VirtualButton::new(center, radius, button));
+        self
+    }
+    pub fn with_gamepad_id(mut self, id: GamepadId) -> Self {
+        self.gamepad_id = id;
+        self
+    }
+    pub fn get_gamepad_id(&self) -> GamepadId {
+        self.gamepad_id
+    }
+    /// Processes the current touches and emits the gamepad events.
+    /// Should be called once per update, before the input is queried.
+    /// The events are not recorded, as they are reproduced from the touches
+    /// when replaying.
+    pub fn update(&mut self, input: &mut InputContext) {
+        let id = self.gamepad_id;
+        if !self.connected {
+            input.handle_derived_event(InputEvent::Gamepad(GamepadEvent::Connected(id)));
+            self.connected = true;
+        }
+        let touches = input.get_touches().clone();
+        let is_active = |touch_id: u64| {
+            touches
+                .get(&touch_id)
+                .is_some_and(|t| !matches!(t.phase, TouchPhase::Ended | TouchPhase::Cancelled))
+        };
+        let mut events = Vec::new();
+
+        for joystick in self.joysticks.iter_mut() {
+            if joystick.touch.is_some_and(|t| !is_active(t)) {
+                joystick.touch = None;
+            }
+            if joystick.touch.is_none() {
+                joystick.touch = touches
+                    .iter()
+                    .find(|(_, t)| {
+                        t.started && (t.position - joystick.center).len() <= joystick.radius
+                    })
+                    .map(|(touch_id, _)| *touch_id);
+            }
+            let value = match joystick.touch.and_then(|t| touches.get(&t)) {
+                Some(touch) => {
+                    let v = (touch.position - joystick.center) / joystick.radius;
+                    if v.len() > 1. {
+                        v.normalized()
+                    } else {
+                        v
+                    }
+                }
+                None => Vector2f::ZERO,
+            };
+            if value != joystick.value {
+                joystick.value = value;
+                events.push(GamepadEvent::StickMoved(id, joystick.stick, value));
+            }
+        }
+
+        for button in self.buttons.iter_mut() {
+            if button.touch.is_some_and(|t| !is_active(t)) {
+                button.touch = None;
+                events.push(GamepadEvent::ButtonReleased(id, button.button));
+            }
+            if button.touch.is_none() {
+                button.touch = touches
+                    .iter()
+                    .find(|(_, t)| t.started && (t.position - button.center).len() <= button.radius)
+                    .map(|(touch_id, _)| *touch_id);
+                if button.touch.is_some() {
+                    events.push(GamepadEvent::ButtonPressed(id, button.button));
+                }
+            }
+        }
+
+        for event in events {
+            input.handle_derived_event(InputEvent::Gamepad(event));
+        }
+    }
+    /// Queues the controls for drawing.
+    /// Positions are converted to the world space of the current camera.
+    pub fn draw(
+        &self,
+        graphics: &mut impl GraphicsContext,
+        z_index: i32,
+    ) -> Result<(), EngineError> {
+        for joystick in self.joysticks.iter() {
+            let color = match joystick.touch {
+                Some(_) => self.pressed_color,
+                None => self.color,
+            };
+            self.draw_circle(
+                graphics,
+                self.joystick_sprite,
+                joystick.center,
+                joystick.radius,
+                z_index,
+                color,
+            )?;
+            self.draw_circle(
+                graphics,
+                self.knob_sprite,
+                joystick.center + joystick.value * joystick.radius,
+                0.5 * joystick.radius,
+                z_index + 1,
+                color,
+            )?;
+        }
+        for button in self.buttons.iter() {
+            let color = match button.touch {
+                Some(_) => self.pressed_color,
+                None => self.color,
+            };
+            self.draw_circle(
+                graphics,
+                self.button_sprite,
+                button.center,
+                button.radius,
+                z_index,
+                color,
+            )?;
+        }
+        Ok(())
+    }
+    fn draw_circle(
+        &self,
+        graphics: &mut impl GraphicsContext,
+        sprite: usize,
+        center: Vector2f,
+        radius: f32,
+        z_index: i32,
+        color: Color,
+    ) -> Result<(), EngineError> {
+        let camera = graphics.get_current_camera();
+        let world_center = camera.camera_to_world(center);
+        // the length is not affected by the camera rotation
+        let world_radius =
+            (camera.camera_to_world(center + Vector2f::new(radius, 0.)) - world_center).len();
+        graphics.draw_atlas_sprite(
+            &self.material,
+            sprite,
+            world_center - Vector2f::splat(world_radius),
+            z_index,
+            Vector2f::splat(2. * world_radius),
+            SpriteParams {
+                color,
+                ..Default::default()
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{Binding, GamepadAxis, InputMap};
+
+    fn touch(input: &mut InputContext, id: u64, phase: TouchPhase, x: f32, y: f32) {
+        input.handle_event(InputEvent::Touch {
+            id,
+            phase,
+            position: Vector2f::new(x, y),
+        });
+    }
+
+    fn controls() -> VirtualControls {
+        VirtualControls::new("ui")
+            .with_joystick(Vector2f::new(100., 100.), 50., GamepadStick::Left)
+            .with_button(Vector2f::new(500., 100.), 30., GamepadButton::South)
+    }
+
+    #[test]
+    fn joystick() {
+        let mut input = InputContext::new();
+        input.set_stick_deadzone(0.);
+        let mut controls = controls();
+
+        // touches starting outside are ignored
+        touch(&mut input, 0, TouchPhase::Started, 300., 100.);
+        controls.update(&mut input);
+        assert!(input.is_gamepad_connected(VIRTUAL_GAMEPAD_ID));
+        assert_eq!(controls.joysticks[0].get_value(), Vector2f::ZERO);
+        input.clear();
+
+        touch(&mut input, 1, TouchPhase::Started, 110., 100.);
+        touch(&mut input, 1, TouchPhase::Moved, 125., 100.);
+        controls.update(&mut input);
+        assert_eq!(
+            input.get_gamepad_stick(VIRTUAL_GAMEPAD_ID, GamepadStick::Left),
+            Vector2f::new(0.5, 0.)
+        );
+        input.clear();
+
+        // clamped to the radius, also when moved outside
+        touch(&mut input, 1, TouchPhase::Moved, 100., -100.);
+        controls.update(&mut input);
+        assert_eq!(controls.joysticks[0].get_value(), Vector2f::new(0., -1.));
+        input.clear();
+
+        touch(&mut input, 1, TouchPhase::Ended, 100., -100.);
+        controls.update(&mut input);
+        assert_eq!(controls.joysticks[0].get_value(), Vector2f::ZERO);
+    }
+
+    #[test]
+    fn button_feeds_input_map() {
+        let mut input = InputContext::new();
+        input.set_input_map(
+            InputMap::new()
+                .with_action("attack", &[Binding::GamepadButton(GamepadButton::South)])
+                .with_axis("move_x", &[], &[])
+                .with_analog_axis("move_x", GamepadAxis::LeftStickX),
+        );
+        let mut controls = controls();
+
+        // started and ended within a single frame
+        touch(&mut input, 1, TouchPhase::Started, 510., 110.);
+        touch(&mut input, 1, TouchPhase::Ended, 510., 110.);
+        assert!(input.is_binding_pressed(Binding::Touch));
+        controls.update(&mut input);
+        assert!(input.is_action_pressed("attack"));
+        input.clear();
+        controls.update(&mut input);
+        assert!(input.is_action_released("attack"));
+        input.clear();
+
+        touch(&mut input, 0, TouchPhase::Started, 510., 110.);
+        controls.update(&mut input);
+        assert!(controls.buttons[0].is_down());
+        assert!(input.is_action_pressed("attack"));
+        input.clear();
+
+        controls.update(&mut input);
+        assert!(input.is_action_down("attack"));
+        assert!(!input.is_action_pressed("attack"));
+        input.clear();
+
+        touch(&mut input, 0, TouchPhase::Cancelled, 510., 110.);
+        controls.update(&mut input);
+        assert!(input.is_action_released("attack"));
+        assert_eq!(input.get_axis("move_x"), 0.);
+    }
+}
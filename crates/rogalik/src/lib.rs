@@ -2,9 +2,12 @@
 
 #[cfg(target_os = "android")]
 mod android;
+pub mod animation;
 mod app;
 mod engine;
 pub mod input;
+#[cfg(feature = "maps")]
+pub mod maps;
 mod scenes;
 mod time;
 mod traits;
@@ -15,7 +18,7 @@ mod wasm;
 pub use winit::platform::android::activity::AndroidApp;
 
 pub use log;
-pub use scenes::SceneController;
+pub use scenes::{SceneController, Transition};
 pub use time::{Instant, Timer};
 pub use traits::{Game, Scene};
 
@@ -26,7 +29,7 @@ pub use rogalik_wgpu as wgpu;
 
 pub mod prelude {
     pub use crate::engine::{Context, EngineBuilder};
-    pub use crate::scenes::SceneController;
+    pub use crate::scenes::{SceneController, Transition};
     pub use crate::traits::{Game, Scene};
     pub use rogalik_assets::{AssetContext, AssetState};
     pub use rogalik_common::*;
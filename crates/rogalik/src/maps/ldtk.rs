@@ -0,0 +1,263 @@
+use serde_json::Value;
+use std::collections::HashMap;
+
+use rogalik_common::EngineError;
+use rogalik_math::vectors::Vector2f;
+
+use super::{
+    json_f32, json_property_value, json_str, json_u32, resolve_path, MapData, MapLayer, MapObject,
+    MapTile, MapTileset, ObjectLayer, Properties, TileLayer,
+};
+
+pub fn parse_ldtk(data: &[u8], path: &str, level: Option<&str>) -> Result<MapData, EngineError> {
+    let root: Value = serde_json::from_slice(data).map_err(|_| EngineError::InvalidResource)?;
+
+    // tileset uid -> map's tileset index
+    let mut tileset_ids = HashMap::new();
+    let mut tilesets = Vec::new();
+    for node in root
+        .pointer("/defs/tilesets")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        // internal (eg. icon) tilesets have no image
+        let Some(image) = node.get("relPath").and_then(Value::as_str) else {
+            continue;
+        };
+        let tile_size = json_u32(node, "tileGridSize").ok_or(EngineError::InvalidResource)?;
+        let columns = json_u32(node, "__cWid").unwrap_or(1);
+        tileset_ids.insert(json_u32(node, "uid"), tilesets.len());
+        tilesets.push(MapTileset {
+            name: json_str(node, "identifier").to_string(),
+            image: resolve_path(path, image),
+            image_width: json_u32(node, "pxWid").unwrap_or_default(),
+            image_height: json_u32(node, "pxHei").unwrap_or_default(),
+            tile_width: tile_size,
+            tile_height: tile_size,
+            spacing: json_u32(node, "spacing").unwrap_or_default(),
+            // LDtk's padding is the margin around the image
+            margin: json_u32(node, "padding").unwrap_or_default(),
+            columns,
+            tile_count: columns * json_u32(node, "__cHei").unwrap_or(1),
+            properties: Properties::new(),
+        });
+    }
+
+    let levels = root
+        .get("levels")
+        .and_then(Value::as_array)
+        .ok_or(EngineError::InvalidResource)?;
+    let level = match level {
+        Some(name) => levels
+            .iter()
+            .find(|l| json_str(l, "identifier") == name)
+            .ok_or(EngineError::ResourceNotFound)?,
+        None => levels.first().ok_or(EngineError::ResourceNotFound)?,
+    };
+    let Some(instances) = level.get("layerInstances").and_then(Value::as_array) else {
+        log::error!("Levels saved in separate files are not supported.");
+        return Err(EngineError::InvalidResource);
+    };
+
+    let grid_size = instances
+        .iter()
+        .find(|l| json_str(l, "__type") != "Entities")
+        .and_then(|l| json_u32(l, "__gridSize"))
+        .or(json_u32(&root, "defaultGridSize"))
+        .unwrap_or(1)
+        .max(1);
+    let height_px = json_f32(level, "pxHei");
+
+    // LDtk lists the top layer first
+    let mut layers = Vec::new();
+    for layer in instances.iter().rev() {
+        match json_str(layer, "__type") {
+            "Entities" => layers.push(MapLayer::Objects(get_object_layer(layer, height_px))),
+            _ => {
+                let tileset = json_u32(layer, "__tilesetDefUid")
+                    .and_then(|uid| tileset_ids.get(&Some(uid)).copied());
+                // IntGrid layers without the auto rules
+                let Some(tileset) = tileset else {
+                    continue;
+                };
+                layers.push(MapLayer::Tiles(get_tile_layer(layer, tileset)?));
+            }
+        }
+    }
+
+    Ok(MapData {
+        width: json_u32(level, "pxWid").unwrap_or_default() / grid_size,
+        height: json_u32(level, "pxHei").unwrap_or_default() / grid_size,
+        tile_width: grid_size,
+        tile_height: grid_size,
+        tilesets,
+        layers,
+        properties: get_fields(level),
+    })
+}
+
+// Tiles, AutoLayer and IntGrid (with the auto rules) layers
+fn get_tile_layer(node: &Value, tileset: usize) -> Result<TileLayer, EngineError> {
+    let width = json_u32(node, "__cWid").ok_or(EngineError::InvalidResource)?;
+    let height = json_u32(node, "__cHei").ok_or(EngineError::InvalidResource)?;
+    let grid_size = json_u32(node, "__gridSize").unwrap_or(1).max(1);
+    let mut tiles = vec![None; (width * height) as usize];
+
+    // stacked tiles are not supported - the top one is kept
+    for tile in ["gridTiles", "autoLayerTiles"]
+        .iter()
+        .filter_map(|key| node.get(*key).and_then(Value::as_array))
+        .flatten()
+    {
+        let Some(px) = tile.get("px").and_then(Value::as_array) else {
+            continue;
+        };
+        let x = px.first().and_then(Value::as_u64).unwrap_or_default() as u32 / grid_size;
+        let y = px.get(1).and_then(Value::as_u64).unwrap_or_default() as u32 / grid_size;
+        if x >= width || y >= height {
+            continue;
+        }
+        let flip = json_u32(tile, "f").unwrap_or_default();
+        // LDtk rows start at the top
+        tiles[((height - 1 - y) * width + x) as usize] = Some(MapTile {
+            tileset,
+            index: json_u32(tile, "t").unwrap_or_default() as usize,
+            flip_x: flip & 1 != 0,
+            flip_y: flip & 2 != 0,
+            flip_diagonal: false,
+        });
+    }
+
+    Ok(TileLayer {
+        name: json_str(node, "__identifier").to_string(),
+        width,
+        height,
+        tiles,
+        properties: Properties::new(),
+    })
+}
+
+fn get_object_layer(node: &Value, height_px: f32) -> ObjectLayer {
+    let objects = node
+        .get("entityInstances")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .map(|(idx, entity)| {
+            let size = Vector2f::new(json_f32(entity, "width"), json_f32(entity, "height"));
+            let px = json_pair(entity, "px");
+            // px points at the entity's pivot
+            let pivot = json_pair(entity, "__pivot");
+            let left = px.x - pivot.x * size.x;
+            let top = px.y - pivot.y * size.y;
+            MapObject {
+                id: idx as u32,
+                name: json_str(entity, "iid").to_string(),
+                class: json_str(entity, "__identifier").to_string(),
+                position: Vector2f::new(left, height_px - top - size.y),
+                size,
+                tile: None,
+                properties: get_fields(entity),
+            }
+        })
+        .collect();
+    ObjectLayer {
+        name: json_str(node, "__identifier").to_string(),
+        objects,
+        properties: Properties::new(),
+    }
+}
+
+fn get_fields(node: &Value) -> Properties {
+    node.get("fieldInstances")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|f| {
+            Some((
+                json_str(f, "__identifier").to_string(),
+                json_property_value(f.get("__value")?)?,
+            ))
+        })
+        .collect()
+}
+
+fn json_pair(node: &Value, key: &str) -> Vector2f {
+    let values = node.get(key).and_then(Value::as_array);
+    let get = |i| {
+        values
+            .and_then(|v| v.get(i))
+            .and_then(Value::as_f64)
+            .unwrap_or_default() as f32
+    };
+    Vector2f::new(get(0), get(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::PropertyValue;
+    use super::*;
+
+    const LDTK: &str = r#"{
+ "defaultGridSize": 8,
+ "defs": {"tilesets": [
+  {"uid": 1, "identifier": "Internal_Icons", "relPath": null},
+  {"uid": 2, "identifier": "Dungeon", "relPath": "../sprites/dungeon.png",
+   "pxWid": 32, "pxHei": 16, "tileGridSize": 8, "spacing": 0, "padding": 0, "__cWid": 4, "__cHei": 2}
+ ]},
+ "levels": [
+  {"identifier": "Level_0", "pxWid": 16, "pxHei": 16, "fieldInstances": [], "layerInstances": []},
+  {"identifier": "Level_1", "pxWid": 24, "pxHei": 16,
+   "fieldInstances": [{"__identifier": "dark", "__value": true}, {"__identifier": "music", "__value": null}],
+   "layerInstances": [
+    {"__identifier": "Entities", "__type": "Entities", "__cWid": 3, "__cHei": 2, "__gridSize": 8,
+     "entityInstances": [
+      {"__identifier": "Player", "iid": "a1", "px": [12, 8], "__pivot": [0.5, 1], "width": 8, "height": 8,
+       "fieldInstances": [{"__identifier": "hp", "__value": 10}]}
+     ]},
+    {"__identifier": "Walls", "__type": "AutoLayer", "__cWid": 3, "__cHei": 2, "__gridSize": 8,
+     "__tilesetDefUid": 2, "gridTiles": [],
+     "autoLayerTiles": [{"px": [16, 0], "t": 5, "f": 2}]},
+    {"__identifier": "Floor", "__type": "Tiles", "__cWid": 3, "__cHei": 2, "__gridSize": 8,
+     "__tilesetDefUid": 2, "gridTiles": [{"px": [0, 8], "t": 1, "f": 0}], "autoLayerTiles": []}
+   ]}
+ ]
+}"#;
+
+    #[test]
+    fn level() {
+        let map = parse_ldtk(LDTK.as_bytes(), "maps/world.ldtk", Some("Level_1")).unwrap();
+        assert_eq!((map.width, map.height, map.tile_width), (3, 2, 8));
+        assert_eq!(map.properties["dark"], PropertyValue::Bool(true));
+        assert!(!map.properties.contains_key("music"));
+
+        assert_eq!(map.tilesets.len(), 1);
+        assert_eq!(map.tilesets[0].image, "sprites/dungeon.png");
+        assert_eq!(map.tilesets[0].tile_count, 8);
+
+        // bottom layer first
+        let MapLayer::Tiles(floor) = &map.layers[0] else {
+            panic!("Floor should be the first layer");
+        };
+        assert_eq!(floor.name, "Floor");
+        assert_eq!(floor.tiles[0].unwrap().index, 1);
+
+        let walls = map.get_tile_layer("Walls").unwrap();
+        let wall = walls.tiles[5].unwrap();
+        assert_eq!((wall.index, wall.flip_x, wall.flip_y), (5, false, true));
+
+        let player = &map.get_object_layer("Entities").unwrap().objects[0];
+        assert_eq!(player.class, "Player");
+        assert_eq!(player.position, Vector2f::new(8., 8.));
+        assert_eq!(player.properties["hp"], PropertyValue::Int(10));
+    }
+
+    #[test]
+    fn missing_level() {
+        assert!(parse_ldtk(LDTK.as_bytes(), "world.ldtk", Some("Level_2")).is_err());
+        let map = parse_ldtk(LDTK.as_bytes(), "world.ldtk", None).unwrap();
+        assert_eq!(map.width, 2);
+    }
+}
@@ -0,0 +1,480 @@
+use std::collections::HashMap;
+
+use rogalik_assets::{AssetContext, AssetState};
+use rogalik_common::{
+    AtlasParams, EngineError, GraphicsContext, MaterialParams, ResourceId, TilemapParams,
+};
+use rogalik_math::vectors::{Vector2f, Vector2i};
+
+mod ldtk;
+mod tiled;
+mod xml;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropertyValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    /// Also the colors, file paths and other non-numeric values.
+    String(String),
+}
+
+pub type Properties = HashMap<String, PropertyValue>;
+
+/// Tileset image, drawn as a sprite atlas.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MapTileset {
+    pub name: String,
+    /// Path relative to the asset root.
+    pub image: String,
+    pub image_width: u32,
+    pub image_height: u32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    /// Space between the tiles, in pixels.
+    pub spacing: u32,
+    /// Space around the tiles (at the image edges), in pixels.
+    pub margin: u32,
+    pub columns: u32,
+    pub tile_count: u32,
+    pub properties: Properties,
+}
+impl MapTileset {
+    /// Atlas of the tileset image, with the tile spacing as the atlas padding
+    /// and the tileset margin as the atlas margin.
+    /// Fails if the image size does not match the tile grid (eg. when the
+    /// margin differs at the opposite edges), as the tiles would be
+    /// misaligned.
+    pub fn get_atlas_params(&self) -> Result<AtlasParams, EngineError> {
+        let cols = self.columns.max(1);
+        let rows = self.get_rows();
+        if !self.fits_atlas() {
+            log::error!(
+                "Tileset `{}` image size {}x{} does not match its tile grid!",
+                self.name,
+                self.image_width,
+                self.image_height
+            );
+            return Err(EngineError::InvalidResource);
+        }
+        let margin = self.margin as f32;
+        Ok(AtlasParams {
+            cols: cols as usize,
+            rows: rows as usize,
+            padding: match self.spacing {
+                0 => None,
+                spacing => Some((spacing as f32, spacing as f32)),
+            },
+            margin: Some((margin, margin)),
+        })
+    }
+    fn get_rows(&self) -> u32 {
+        self.tile_count.div_ceil(self.columns.max(1)).max(1)
+    }
+    fn fits_atlas(&self) -> bool {
+        let grid_size =
+            |count: u32, tile: u32| 2 * self.margin + count * tile + (count - 1) * self.spacing;
+        self.image_width == grid_size(self.columns.max(1), self.tile_width)
+            && self.image_height == grid_size(self.get_rows(), self.tile_height)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MapTile {
+    /// Index of the map's tileset.
+    pub tileset: usize,
+    /// Atlas index within the tileset.
+    pub index: usize,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /// Flipped over the top-left to bottom-right diagonal (applied before
+    /// `flip_x` and `flip_y`), eg. for the tiles rotated in Tiled.
+    /// Not supported by the sprites and the tilemaps.
+    pub flip_diagonal: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TileLayer {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /// Row by row, starting from the bottom one (as the y axis points up).
+    pub tiles: Vec<Option<MapTile>>,
+    pub properties: Properties,
+}
+impl TileLayer {
+    pub fn get(&self, v: Vector2i) -> Option<MapTile> {
+        if v.x < 0 || v.y < 0 || v.x >= self.width as i32 || v.y >= self.height as i32 {
+            return None;
+        }
+        self.tiles[(v.y as u32 * self.width + v.x as u32) as usize]
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MapObject {
+    /// Object id (for LDtk: the entity's index in the layer).
+    pub id: u32,
+    pub name: String,
+    /// Tiled object's class (type) or LDtk entity's identifier.
+    pub class: String,
+    /// Bottom-left corner, in pixels (with the y axis pointing up).
+    pub position: Vector2f,
+    pub size: Vector2f,
+    pub tile: Option<MapTile>,
+    pub properties: Properties,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ObjectLayer {
+    pub name: String,
+    pub objects: Vec<MapObject>,
+    pub properties: Properties,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MapLayer {
+    Tiles(TileLayer),
+    Objects(ObjectLayer),
+}
+
+/// Map (or LDtk level) data, independent of the editor.
+/// Layers are ordered from the bottom one.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MapData {
+    /// Width in tiles.
+    pub width: u32,
+    /// Height in tiles.
+    pub height: u32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub tilesets: Vec<MapTileset>,
+    pub layers: Vec<MapLayer>,
+    pub properties: Properties,
+}
+impl MapData {
+    pub fn get_tile_layer(&self, name: &str) -> Option<&TileLayer> {
+        self.layers.iter().find_map(|l| match l {
+            MapLayer::Tiles(layer) if layer.name == name => Some(layer),
+            _ => None,
+        })
+    }
+    pub fn get_object_layer(&self, name: &str) -> Option<&ObjectLayer> {
+        self.layers.iter().find_map(|l| match l {
+            MapLayer::Objects(layer) if layer.name == name => Some(layer),
+            _ => None,
+        })
+    }
+    pub fn get_tile_layers(&self) -> impl Iterator<Item = &TileLayer> {
+        self.layers.iter().filter_map(|l| match l {
+            MapLayer::Tiles(layer) => Some(layer),
+            _ => None,
+        })
+    }
+    /// Loads the tileset textures and creates a material (with an atlas) for
+    /// each tileset, named as the tileset.
+    /// Should be called in the game's setup, with the other assets.
+    /// Fails on the first tileset not matching its atlas grid.
+    pub fn load_materials(&self, graphics: &mut impl GraphicsContext) -> Result<(), EngineError> {
+        for tileset in self.tilesets.iter() {
+            let atlas = tileset.get_atlas_params()?;
+            let texture = graphics.load_texture(&tileset.image);
+            graphics.load_material(
+                &tileset.name,
+                MaterialParams {
+                    atlas: Some(atlas),
+                    diffuse_texture: Some(texture),
+                    ..Default::default()
+                },
+            );
+        }
+        Ok(())
+    }
+    /// Creates a tilemap of the tileset's tiles (using the tileset's
+    /// material), with a layer for each tile layer of the map.
+    /// The layers' z-indices start at `z_index`. Tiles are sized in pixels.
+    /// Flipped (and rotated) tiles are not supported by the tilemap and are
+    /// drawn as is.
+    pub fn create_tilemap(
+        &self,
+        graphics: &mut impl GraphicsContext,
+        tileset: usize,
+        z_index: i32,
+    ) -> Result<ResourceId, EngineError> {
+        let material = &self
+            .tilesets
+            .get(tileset)
+            .ok_or(EngineError::ResourceNotFound)?
+            .name;
+        let layers = self.get_tile_layers().count() as i32;
+        let tilemap = graphics.create_tilemap(
+            material,
+            TilemapParams {
+                width: self.width,
+                height: self.height,
+                tile_size: Vector2f::new(self.tile_width as f32, self.tile_height as f32),
+                layers: (z_index..z_index + layers).collect(),
+                ..Default::default()
+            },
+        )?;
+        self.update_tilemap(graphics, tilemap, tileset)?;
+        Ok(tilemap)
+    }
+    /// Sets the tiles of a tilemap created by `create_tilemap`
+    /// (eg. after the map is reloaded).
+    pub fn update_tilemap(
+        &self,
+        graphics: &mut impl GraphicsContext,
+        tilemap: ResourceId,
+        tileset: usize,
+    ) -> Result<(), EngineError> {
+        for (idx, layer) in self.get_tile_layers().enumerate() {
+            for y in 0..self.height as i32 {
+                for x in 0..self.width as i32 {
+                    let v = Vector2i::new(x, y);
+                    let tile = layer
+                        .get(v)
+                        .filter(|t| t.tileset == tileset)
+                        .map(|t| t.index);
+                    graphics.set_tile(tilemap, idx, v, tile)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Map loaded through the asset store: a Tiled map (`.tmx` or `.tmj`, with
+/// the embedded or external tilesets) or an LDtk level (`.ldtk`).
+/// Tile layers must use the CSV (or plain) encoding and LDtk levels can not
+/// be saved in separate files.
+pub struct MapAsset {
+    pub data: MapData,
+    path: String,
+    level: Option<String>,
+    // the map and the external tileset files
+    asset_ids: HashMap<String, ResourceId>,
+}
+impl MapAsset {
+    /// Loads the map. For LDtk files the first level is used.
+    pub fn load(store: &mut impl AssetContext, path: &str) -> Result<Self, EngineError> {
+        Self::load_level(store, path, None)
+    }
+    /// Loads the level (by its identifier) from an LDtk file.
+    pub fn load_ldtk_level(
+        store: &mut impl AssetContext,
+        path: &str,
+        level: &str,
+    ) -> Result<Self, EngineError> {
+        Self::load_level(store, path, Some(level))
+    }
+    /// Re-parses the map if any of its files has been reloaded
+    /// (eg. by the F5 dev reload). To be called in `Game::reload_assets`.
+    /// Returns `true` if the map data has changed.
+    pub fn update(&mut self, store: &mut impl AssetContext) -> Result<bool, EngineError> {
+        let updated = self.asset_ids.values().any(|id| {
+            store
+                .get(*id)
+                .is_some_and(|a| a.state == AssetState::Updated)
+        });
+        if !updated {
+            return Ok(false);
+        }
+        self.data = parse(
+            store,
+            &mut self.asset_ids,
+            &self.path,
+            self.level.as_deref(),
+        )?;
+        for id in self.asset_ids.values() {
+            store.mark_read(*id);
+        }
+        Ok(true)
+    }
+    fn load_level(
+        store: &mut impl AssetContext,
+        path: &str,
+        level: Option<&str>,
+    ) -> Result<Self, EngineError> {
+        let mut asset_ids = HashMap::new();
+        let data = parse(store, &mut asset_ids, path, level)?;
+        Ok(Self {
+            data,
+            path: path.to_string(),
+            level: level.map(|l| l.to_string()),
+            asset_ids,
+        })
+    }
+}
+
+fn parse(
+    store: &mut impl AssetContext,
+    asset_ids: &mut HashMap<String, ResourceId>,
+    path: &str,
+    level: Option<&str>,
+) -> Result<MapData, EngineError> {
+    // files are loaded once and re-read from the store afterwards
+    let mut load = |path: &str| -> Result<Vec<u8>, EngineError> {
+        let id = match asset_ids.get(path) {
+            Some(id) => *id,
+            None => {
+                let id = store.load(path)?;
+                asset_ids.insert(path.to_string(), id);
+                id
+            }
+        };
+        Ok(store
+            .get(id)
+            .ok_or(EngineError::ResourceNotFound)?
+            .data
+            .get()
+            .to_vec())
+    };
+    let data = load(path)?;
+    let result = match path.rsplit('.').next() {
+        Some("tmx") => tiled::parse_tmx(&data, path, &mut load),
+        Some("tmj") | Some("json") => tiled::parse_tmj(&data, path, &mut load),
+        Some("ldtk") => ldtk::parse_ldtk(&data, path, level),
+        _ => Err(EngineError::InvalidResource),
+    };
+    if result.is_err() {
+        log::error!("Can't parse the map: {}", path);
+    }
+    result
+}
+
+// typed Tiled property (also used for the LDtk fields)
+fn property_value(kind: &str, value: &str) -> PropertyValue {
+    let parsed = match kind {
+        "bool" => value.parse().ok().map(PropertyValue::Bool),
+        "int" | "object" => value.parse().ok().map(PropertyValue::Int),
+        "float" => value.parse().ok().map(PropertyValue::Float),
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| PropertyValue::String(value.to_string()))
+}
+
+fn json_property_value(value: &serde_json::Value) -> Option<PropertyValue> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::Bool(b) => Some(PropertyValue::Bool(*b)),
+        serde_json::Value::Number(n) => Some(match n.as_i64() {
+            Some(i) => PropertyValue::Int(i),
+            None => PropertyValue::Float(n.as_f64()?),
+        }),
+        serde_json::Value::String(s) => Some(PropertyValue::String(s.clone())),
+        // arrays and objects are kept as the raw json
+        _ => Some(PropertyValue::String(value.to_string())),
+    }
+}
+
+// Tiled's `properties` array
+fn json_properties(node: &serde_json::Value) -> Properties {
+    node.get("properties")
+        .and_then(serde_json::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|p| {
+            let value = p.get("value")?;
+            let value = match (json_str(p, "type"), value.as_f64()) {
+                ("float", Some(f)) => PropertyValue::Float(f),
+                _ => json_property_value(value)?,
+            };
+            Some((json_str(p, "name").to_string(), value))
+        })
+        .collect()
+}
+
+fn json_u32(node: &serde_json::Value, key: &str) -> Option<u32> {
+    node.get(key)?.as_u64().map(|v| v as u32)
+}
+
+fn json_f32(node: &serde_json::Value, key: &str) -> f32 {
+    node.get(key)
+        .and_then(serde_json::Value::as_f64)
+        .unwrap_or_default() as f32
+}
+
+fn json_str<'a>(node: &'a serde_json::Value, key: &str) -> &'a str {
+    node.get(key)
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or_default()
+}
+
+/// Resolves the `relative` path against the directory of the `base` file.
+fn resolve_path(base: &str, relative: &str) -> String {
+    let mut parts = base.split('/').collect::<Vec<_>>();
+    // file name
+    parts.pop();
+    for part in relative.split('/') {
+        match part {
+            "." | "" => (),
+            ".." if parts.last().is_some_and(|p| *p != "..") => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_relative_paths() {
+        assert_eq!(
+            resolve_path("maps/level.tmx", "tiles.png"),
+            "maps/tiles.png"
+        );
+        assert_eq!(
+            resolve_path("maps/levels/a.tmx", "../tilesets/./tiles.tsx"),
+            "maps/tilesets/tiles.tsx"
+        );
+        assert_eq!(resolve_path("level.tmx", "../tiles.png"), "../tiles.png");
+    }
+
+    #[test]
+    fn atlas_params() {
+        let tileset = MapTileset {
+            image_width: 35,
+            image_height: 17,
+            tile_width: 8,
+            tile_height: 8,
+            spacing: 1,
+            columns: 4,
+            tile_count: 8,
+            ..Default::default()
+        };
+        let atlas = tileset.get_atlas_params().unwrap();
+        assert_eq!((atlas.cols, atlas.rows), (4, 2));
+        assert_eq!(atlas.padding, Some((1., 1.)));
+        assert_eq!(atlas.margin, Some((0., 0.)));
+
+        let tileset = MapTileset {
+            image_width: 37,
+            image_height: 19,
+            margin: 1,
+            ..tileset
+        };
+        let atlas = tileset.get_atlas_params().unwrap();
+        assert_eq!(atlas.margin, Some((1., 1.)));
+
+        // margin at the top-left edges only
+        let tileset = MapTileset {
+            image_width: 36,
+            image_height: 18,
+            ..tileset
+        };
+        assert!(tileset.get_atlas_params().is_err());
+        // spacing after the last column and row
+        let tileset = MapTileset {
+            image_width: 36,
+            image_height: 18,
+            margin: 0,
+            ..tileset
+        };
+        assert!(tileset.get_atlas_params().is_err());
+    }
+}
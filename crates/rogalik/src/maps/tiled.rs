@@ -0,0 +1,517 @@
+use serde_json::Value;
+
+use rogalik_common::EngineError;
+use rogalik_math::vectors::Vector2f;
+
+use super::xml::{parse_xml, XmlNode};
+use super::{
+    json_f32, json_properties, json_str, json_u32, property_value, resolve_path, MapData, MapLayer,
+    MapObject, MapTile, MapTileset, ObjectLayer, Properties, TileLayer,
+};
+
+const FLIP_X: u32 = 0x80000000;
+const FLIP_Y: u32 = 0x40000000;
+const FLIP_DIAGONAL: u32 = 0x20000000;
+const ROTATE_HEX: u32 = 0x10000000;
+
+pub fn parse_tmx(
+    data: &[u8],
+    path: &str,
+    load: &mut impl FnMut(&str) -> Result<Vec<u8>, EngineError>,
+) -> Result<MapData, EngineError> {
+    let root = parse_xml(data)?;
+    if root.name != "map" || root.attr("infinite") == Some("1") {
+        return Err(EngineError::InvalidResource);
+    }
+    let mut map = MapData {
+        width: root
+            .parse_attr("width")
+            .ok_or(EngineError::InvalidResource)?,
+        height: root
+            .parse_attr("height")
+            .ok_or(EngineError::InvalidResource)?,
+        tile_width: root
+            .parse_attr("tilewidth")
+            .ok_or(EngineError::InvalidResource)?,
+        tile_height: root
+            .parse_attr("tileheight")
+            .ok_or(EngineError::InvalidResource)?,
+        properties: xml_properties(&root),
+        ..Default::default()
+    };
+
+    let mut first_gids = Vec::new();
+    for node in root.children.iter().filter(|c| c.name == "tileset") {
+        first_gids.push(
+            node.parse_attr("firstgid")
+                .ok_or(EngineError::InvalidResource)?,
+        );
+        let tileset = match node.attr("source") {
+            Some(source) => {
+                let tileset_path = resolve_path(path, source);
+                parse_external_tileset(&load(&tileset_path)?, &tileset_path)?
+            }
+            None => xml_tileset(node, path)?,
+        };
+        map.tilesets.push(tileset);
+    }
+
+    let height_px = (map.height * map.tile_height) as f32;
+    xml_layers(&root, &first_gids, height_px, &mut map.layers)?;
+    Ok(map)
+}
+
+pub fn parse_tmj(
+    data: &[u8],
+    path: &str,
+    load: &mut impl FnMut(&str) -> Result<Vec<u8>, EngineError>,
+) -> Result<MapData, EngineError> {
+    let root: Value = serde_json::from_slice(data).map_err(|_| EngineError::InvalidResource)?;
+    if root.get("infinite").and_then(Value::as_bool) == Some(true) {
+        return Err(EngineError::InvalidResource);
+    }
+    let mut map = MapData {
+        width: json_u32(&root, "width").ok_or(EngineError::InvalidResource)?,
+        height: json_u32(&root, "height").ok_or(EngineError::InvalidResource)?,
+        tile_width: json_u32(&root, "tilewidth").ok_or(EngineError::InvalidResource)?,
+        tile_height: json_u32(&root, "tileheight").ok_or(EngineError::InvalidResource)?,
+        properties: json_properties(&root),
+        ..Default::default()
+    };
+
+    let mut first_gids = Vec::new();
+    for node in root
+        .get("tilesets")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        first_gids.push(json_u32(node, "firstgid").ok_or(EngineError::InvalidResource)?);
+        let tileset = match node.get("source").and_then(Value::as_str) {
+            Some(source) => {
+                let tileset_path = resolve_path(path, source);
+                parse_external_tileset(&load(&tileset_path)?, &tileset_path)?
+            }
+            None => json_tileset(node, path)?,
+        };
+        map.tilesets.push(tileset);
+    }
+
+    let height_px = (map.height * map.tile_height) as f32;
+    json_layers(&root, &first_gids, height_px, &mut map.layers)?;
+    Ok(map)
+}
+
+/// `.tsx` or `.tsj` tileset
+fn parse_external_tileset(data: &[u8], path: &str) -> Result<MapTileset, EngineError> {
+    if path.ends_with(".tsx") {
+        return xml_tileset(&parse_xml(data)?, path);
+    }
+    let root: Value = serde_json::from_slice(data).map_err(|_| EngineError::InvalidResource)?;
+    json_tileset(&root, path)
+}
+
+fn get_tile(gid: u32, first_gids: &[u32]) -> Option<MapTile> {
+    let id = gid & !(FLIP_X | FLIP_Y | FLIP_DIAGONAL | ROTATE_HEX);
+    if id == 0 {
+        return None;
+    }
+    // tilesets are sorted by the first gid
+    let tileset = first_gids.iter().rposition(|&first| first <= id)?;
+    Some(MapTile {
+        tileset,
+        index: (id - first_gids[tileset]) as usize,
+        flip_x: gid & FLIP_X != 0,
+        flip_y: gid & FLIP_Y != 0,
+        flip_diagonal: gid & FLIP_DIAGONAL != 0,
+    })
+}
+
+// Tiled rows start at the top
+fn get_tiles(gids: &[u32], width: u32, first_gids: &[u32]) -> Vec<Option<MapTile>> {
+    gids.chunks(width.max(1) as usize)
+        .rev()
+        .flatten()
+        .map(|&gid| get_tile(gid, first_gids))
+        .collect()
+}
+
+// Tiled positions are measured from the top-left corner
+fn get_object_position(x: f32, y: f32, height: f32, is_tile: bool, height_px: f32) -> Vector2f {
+    match is_tile {
+        // tile objects are anchored at the bottom-left corner
+        true => Vector2f::new(x, height_px - y),
+        false => Vector2f::new(x, height_px - y - height),
+    }
+}
+
+fn xml_tileset(node: &XmlNode, path: &str) -> Result<MapTileset, EngineError> {
+    // image collections are not supported
+    let image = node.child("image").ok_or(EngineError::InvalidResource)?;
+    Ok(MapTileset {
+        name: node.attr("name").unwrap_or_default().to_string(),
+        image: resolve_path(
+            path,
+            image.attr("source").ok_or(EngineError::InvalidResource)?,
+        ),
+        image_width: image.parse_attr("width").unwrap_or_default(),
+        image_height: image.parse_attr("height").unwrap_or_default(),
+        tile_width: node
+            .parse_attr("tilewidth")
+            .ok_or(EngineError::InvalidResource)?,
+        tile_height: node
+            .parse_attr("tileheight")
+            .ok_or(EngineError::InvalidResource)?,
+        spacing: node.parse_attr("spacing").unwrap_or_default(),
+        margin: node.parse_attr("margin").unwrap_or_default(),
+        columns: node.parse_attr("columns").unwrap_or(1),
+        tile_count: node.parse_attr("tilecount").unwrap_or(1),
+        properties: xml_properties(node),
+    })
+}
+
+fn xml_properties(node: &XmlNode) -> Properties {
+    node.child("properties")
+        .into_iter()
+        .flat_map(|p| p.children.iter())
+        .filter_map(|p| {
+            let name = p.attr("name")?.to_string();
+            // multiline strings are stored as the element's text
+            let value = p.attr("value").unwrap_or(&p.text);
+            Some((
+                name,
+                property_value(p.attr("type").unwrap_or("string"), value),
+            ))
+        })
+        .collect()
+}
+
+fn xml_layers(
+    node: &XmlNode,
+    first_gids: &[u32],
+    height_px: f32,
+    layers: &mut Vec<MapLayer>,
+) -> Result<(), EngineError> {
+    for child in node.children.iter() {
+        match child.name.as_str() {
+            "layer" => layers.push(MapLayer::Tiles(xml_tile_layer(child, first_gids)?)),
+            "objectgroup" => layers.push(MapLayer::Objects(xml_object_layer(
+                child, first_gids, height_px,
+            ))),
+            // the groups are flattened
+            "group" => xml_layers(child, first_gids, height_px, layers)?,
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+fn xml_tile_layer(node: &XmlNode, first_gids: &[u32]) -> Result<TileLayer, EngineError> {
+    let width = node
+        .parse_attr("width")
+        .ok_or(EngineError::InvalidResource)?;
+    let height = node
+        .parse_attr("height")
+        .ok_or(EngineError::InvalidResource)?;
+    let data = node.child("data").ok_or(EngineError::InvalidResource)?;
+    let gids = match data.attr("encoding") {
+        Some("csv") => data
+            .text
+            .split(',')
+            .map(|gid| gid.trim().parse().map_err(|_| EngineError::InvalidResource))
+            .collect::<Result<Vec<u32>, _>>()?,
+        None => data
+            .children
+            .iter()
+            .map(|t| t.parse_attr("gid").unwrap_or(0))
+            .collect(),
+        Some(_) => {
+            log::error!("Unsupported tile layer encoding, use CSV.");
+            return Err(EngineError::InvalidResource);
+        }
+    };
+    if gids.len() != (width * height) as usize {
+        return Err(EngineError::InvalidResource);
+    }
+    Ok(TileLayer {
+        name: node.attr("name").unwrap_or_default().to_string(),
+        width,
+        height,
+        tiles: get_tiles(&gids, width, first_gids),
+        properties: xml_properties(node),
+    })
+}
+
+fn xml_object_layer(node: &XmlNode, first_gids: &[u32], height_px: f32) -> ObjectLayer {
+    let objects = node
+        .children
+        .iter()
+        .filter(|c| c.name == "object")
+        .map(|o| {
+            let tile = o
+                .parse_attr("gid")
+                .and_then(|gid| get_tile(gid, first_gids));
+            let size = Vector2f::new(
+                o.parse_attr("width").unwrap_or_default(),
+                o.parse_attr("height").unwrap_or_default(),
+            );
+            MapObject {
+                id: o.parse_attr("id").unwrap_or_default(),
+                name: o.attr("name").unwrap_or_default().to_string(),
+                class: o
+                    .attr("type")
+                    .or(o.attr("class"))
+                    .unwrap_or_default()
+                    .to_string(),
+                position: get_object_position(
+                    o.parse_attr("x").unwrap_or_default(),
+                    o.parse_attr("y").unwrap_or_default(),
+                    size.y,
+                    tile.is_some(),
+                    height_px,
+                ),
+                size,
+                tile,
+                properties: xml_properties(o),
+            }
+        })
+        .collect();
+    ObjectLayer {
+        name: node.attr("name").unwrap_or_default().to_string(),
+        objects,
+        properties: xml_properties(node),
+    }
+}
+
+fn json_tileset(node: &Value, path: &str) -> Result<MapTileset, EngineError> {
+    Ok(MapTileset {
+        name: json_str(node, "name").to_string(),
+        image: resolve_path(
+            path,
+            node.get("image")
+                .and_then(Value::as_str)
+                .ok_or(EngineError::InvalidResource)?,
+        ),
+        image_width: json_u32(node, "imagewidth").unwrap_or_default(),
+        image_height: json_u32(node, "imageheight").unwrap_or_default(),
+        tile_width: json_u32(node, "tilewidth").ok_or(EngineError::InvalidResource)?,
+        tile_height: json_u32(node, "tileheight").ok_or(EngineError::InvalidResource)?,
+        spacing: json_u32(node, "spacing").unwrap_or_default(),
+        margin: json_u32(node, "margin").unwrap_or_default(),
+        columns: json_u32(node, "columns").unwrap_or(1),
+        tile_count: json_u32(node, "tilecount").unwrap_or(1),
+        properties: json_properties(node),
+    })
+}
+
+fn json_layers(
+    node: &Value,
+    first_gids: &[u32],
+    height_px: f32,
+    layers: &mut Vec<MapLayer>,
+) -> Result<(), EngineError> {
+    for layer in node
+        .get("layers")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        match json_str(layer, "type") {
+            "tilelayer" => layers.push(MapLayer::Tiles(json_tile_layer(layer, first_gids)?)),
+            "objectgroup" => layers.push(MapLayer::Objects(json_object_layer(
+                layer, first_gids, height_px,
+            ))),
+            // the groups are flattened
+            "group" => json_layers(layer, first_gids, height_px, layers)?,
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+fn json_tile_layer(node: &Value, first_gids: &[u32]) -> Result<TileLayer, EngineError> {
+    let width = json_u32(node, "width").ok_or(EngineError::InvalidResource)?;
+    let height = json_u32(node, "height").ok_or(EngineError::InvalidResource)?;
+    let Some(data) = node.get("data").and_then(Value::as_array) else {
+        log::error!("Unsupported tile layer encoding, use CSV.");
+        return Err(EngineError::InvalidResource);
+    };
+    let gids = data
+        .iter()
+        .map(|gid| gid.as_u64().map(|gid| gid as u32))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(EngineError::InvalidResource)?;
+    if gids.len() != (width * height) as usize {
+        return Err(EngineError::InvalidResource);
+    }
+    Ok(TileLayer {
+        name: json_str(node, "name").to_string(),
+        width,
+        height,
+        tiles: get_tiles(&gids, width, first_gids),
+        properties: json_properties(node),
+    })
+}
+
+fn json_object_layer(node: &Value, first_gids: &[u32], height_px: f32) -> ObjectLayer {
+    let objects = node
+        .get("objects")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|o| {
+            let tile = json_u32(o, "gid").and_then(|gid| get_tile(gid, first_gids));
+            let size = Vector2f::new(json_f32(o, "width"), json_f32(o, "height"));
+            let class = match json_str(o, "type") {
+                "" => json_str(o, "class"),
+                class => class,
+            };
+            MapObject {
+                id: json_u32(o, "id").unwrap_or_default(),
+                name: json_str(o, "name").to_string(),
+                class: class.to_string(),
+                position: get_object_position(
+                    json_f32(o, "x"),
+                    json_f32(o, "y"),
+                    size.y,
+                    tile.is_some(),
+                    height_px,
+                ),
+                size,
+                tile,
+                properties: json_properties(o),
+            }
+        })
+        .collect();
+    ObjectLayer {
+        name: json_str(node, "name").to_string(),
+        objects,
+        properties: json_properties(node),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::PropertyValue;
+    use super::*;
+
+    const TSX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" name="dungeon" tilewidth="8" tileheight="8" tilecount="4" columns="4">
+ <image source="../sprites/dungeon.png" width="32" height="8"/>
+</tileset>"#;
+
+    const TMX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="3" height="2" tilewidth="8" tileheight="8" infinite="0">
+ <properties>
+  <property name="dark" type="bool" value="true"/>
+  <property name="note">multi
+line</property>
+ </properties>
+ <tileset firstgid="1" source="dungeon.tsx"/>
+ <tileset firstgid="5" name="items" tilewidth="8" tileheight="8" spacing="1" tilecount="2" columns="2">
+  <image source="items.png" width="17" height="8"/>
+ </tileset>
+ <layer id="1" name="floor" width="3" height="2">
+  <data encoding="csv">
+1,2,0,
+6,2147483650,3
+</data>
+ </layer>
+ <group id="3" name="group">
+  <objectgroup id="2" name="entities">
+   <object id="4" name="player" type="Actor" x="8" y="4" width="8" height="4">
+    <properties>
+     <property name="hp" type="int" value="10"/>
+    </properties>
+   </object>
+   <object id="5" gid="5" x="16" y="16" width="8" height="8"/>
+  </objectgroup>
+ </group>
+</map>"#;
+
+    const TMJ: &str = r#"{
+ "width": 3, "height": 2, "tilewidth": 8, "tileheight": 8, "infinite": false,
+ "properties": [{"name": "speed", "type": "float", "value": 1}],
+ "tilesets": [{"firstgid": 1, "source": "dungeon.tsx"}],
+ "layers": [
+  {"type": "tilelayer", "name": "floor", "width": 3, "height": 2, "data": [1, 2, 0, 4, 4, 536870915]},
+  {"type": "objectgroup", "name": "entities", "objects": [
+   {"id": 1, "name": "door", "type": "Door", "x": 0, "y": 8, "width": 8, "height": 8,
+    "properties": [{"name": "locked", "type": "bool", "value": true}]}
+  ]}
+ ]
+}"#;
+
+    fn load(path: &str) -> Result<Vec<u8>, EngineError> {
+        assert_eq!(path, "maps/dungeon.tsx");
+        Ok(TSX.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn tmx() {
+        let map = parse_tmx(TMX.as_bytes(), "maps/level.tmx", &mut load).unwrap();
+        assert_eq!((map.width, map.height), (3, 2));
+        assert_eq!(map.properties["dark"], PropertyValue::Bool(true));
+        assert_eq!(
+            map.properties["note"],
+            PropertyValue::String("multi\nline".to_string())
+        );
+
+        assert_eq!(map.tilesets[0].image, "sprites/dungeon.png");
+        assert_eq!(map.tilesets[1].image, "maps/items.png");
+        assert_eq!(map.tilesets[1].spacing, 1);
+        let atlas = map.tilesets[1].get_atlas_params().unwrap();
+        assert_eq!((atlas.cols, atlas.rows), (2, 1));
+        assert_eq!(atlas.padding, Some((1., 1.)));
+
+        let floor = map.get_tile_layer("floor").unwrap();
+        // the bottom row first
+        assert_eq!(
+            floor.tiles[0],
+            Some(MapTile {
+                tileset: 1,
+                index: 1,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            floor.tiles[1],
+            Some(MapTile {
+                tileset: 0,
+                index: 1,
+                flip_x: true,
+                flip_y: false,
+                flip_diagonal: false,
+            })
+        );
+        assert_eq!(floor.tiles[5], None);
+
+        let entities = map.get_object_layer("entities").unwrap();
+        let player = &entities.objects[0];
+        assert_eq!(player.class, "Actor");
+        assert_eq!(player.position, Vector2f::new(8., 8.));
+        assert_eq!(player.properties["hp"], PropertyValue::Int(10));
+        // tile objects are anchored at the bottom
+        assert_eq!(entities.objects[1].position, Vector2f::new(16., 0.));
+        assert_eq!(entities.objects[1].tile.unwrap().tileset, 1);
+    }
+
+    #[test]
+    fn tmj() {
+        let map = parse_tmj(TMJ.as_bytes(), "maps/level.tmj", &mut load).unwrap();
+        assert_eq!(map.properties["speed"], PropertyValue::Float(1.));
+        assert_eq!(map.tilesets[0].name, "dungeon");
+
+        let floor = map.get_tile_layer("floor").unwrap();
+        assert_eq!(floor.tiles[0].unwrap().index, 3);
+        assert_eq!(floor.tiles[3].unwrap().index, 0);
+        // rotated by 90 degrees
+        let rotated = floor.tiles[2].unwrap();
+        assert_eq!(rotated.index, 2);
+        assert!(rotated.flip_diagonal);
+
+        let door = &map.get_object_layer("entities").unwrap().objects[0];
+        assert_eq!(door.position, Vector2f::new(0., 0.));
+        assert_eq!(door.properties["locked"], PropertyValue::Bool(true));
+    }
+}
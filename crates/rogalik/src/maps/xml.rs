@@ -0,0 +1,98 @@
+use quick_xml::events::{BytesStart, Event};
+use std::collections::HashMap;
+
+use rogalik_common::EngineError;
+
+/// Minimal XML element tree (enough for the Tiled files).
+#[derive(Debug, Default)]
+pub struct XmlNode {
+    pub name: String,
+    pub attrs: HashMap<String, String>,
+    pub children: Vec<XmlNode>,
+    pub text: String,
+}
+impl XmlNode {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.get(name).map(|a| a.as_str())
+    }
+    pub fn parse_attr<T: std::str::FromStr>(&self, name: &str) -> Option<T> {
+        self.attr(name)?.parse().ok()
+    }
+    pub fn child(&self, name: &str) -> Option<&XmlNode> {
+        self.children.iter().find(|c| c.name == name)
+    }
+}
+
+/// Returns the root element.
+pub fn parse_xml(data: &[u8]) -> Result<XmlNode, EngineError> {
+    let mut reader = quick_xml::Reader::from_reader(data);
+    reader.config_mut().trim_text(true);
+    // currently open elements
+    let mut stack = vec![XmlNode::default()];
+
+    loop {
+        match reader
+            .read_event()
+            .map_err(|_| EngineError::InvalidResource)?
+        {
+            Event::Start(e) => stack.push(get_node(&e)?),
+            Event::Empty(e) => {
+                let node = get_node(&e)?;
+                stack
+                    .last_mut()
+                    .ok_or(EngineError::InvalidResource)?
+                    .children
+                    .push(node);
+            }
+            Event::Text(t) => {
+                let text = t.unescape().map_err(|_| EngineError::InvalidResource)?;
+                stack
+                    .last_mut()
+                    .ok_or(EngineError::InvalidResource)?
+                    .text
+                    .push_str(&text);
+            }
+            Event::CData(t) => {
+                let text = String::from_utf8_lossy(&t);
+                stack
+                    .last_mut()
+                    .ok_or(EngineError::InvalidResource)?
+                    .text
+                    .push_str(&text);
+            }
+            Event::End(_) => {
+                let node = stack.pop().ok_or(EngineError::InvalidResource)?;
+                stack
+                    .last_mut()
+                    .ok_or(EngineError::InvalidResource)?
+                    .children
+                    .push(node);
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+    stack
+        .pop()
+        .and_then(|document| document.children.into_iter().next())
+        .ok_or(EngineError::InvalidResource)
+}
+
+fn get_node(e: &BytesStart) -> Result<XmlNode, EngineError> {
+    let mut attrs = HashMap::new();
+    for attr in e.attributes() {
+        let attr = attr.map_err(|_| EngineError::InvalidResource)?;
+        let value = attr
+            .unescape_value()
+            .map_err(|_| EngineError::InvalidResource)?;
+        attrs.insert(
+            String::from_utf8_lossy(attr.key.as_ref()).to_string(),
+            value.to_string(),
+        );
+    }
+    Ok(XmlNode {
+        name: String::from_utf8_lossy(e.name().as_ref()).to_string(),
+        attrs,
+        ..Default::default()
+    })
+}
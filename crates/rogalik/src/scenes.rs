@@ -1,3 +1,5 @@
+use rogalik_common::{traits::GraphicsSetup, BuiltInShader, GraphicsContext, ResourceId};
+
 use crate::{
     engine::Context,
     traits::{Game, Scene, SceneChange},
@@ -5,31 +7,92 @@ use crate::{
 
 const EMPTY_STACK_MSG: &str = "Scene stack is empty!";
 
-pub struct SceneController<T: Game>(Option<SceneChange<T>>);
+/// Animated scene change.
+/// Both the outgoing and the incoming scenes are drawn for the duration
+/// (in seconds) of the transition and blended by a post process shader.
+/// Scene updates are paused until the transition is finished.
+#[derive(Clone, Copy, Debug)]
+pub enum Transition {
+    /// Cross fade between the scenes.
+    Fade(f32),
+    /// Incoming scene is revealed from left to right.
+    Wipe(f32),
+    /// User supplied `ShaderKind::PostProcess` shader.
+    /// The incoming scene is bound as the input image (binding 0),
+    /// the outgoing one as the texture (binding 2)
+    /// and the transition progress (0.0 to 1.0) as the strength uniform.
+    Custom(ResourceId, f32),
+}
+impl Transition {
+    fn duration(&self) -> f32 {
+        match self {
+            Self::Fade(d) | Self::Wipe(d) | Self::Custom(_, d) => *d,
+        }
+    }
+    fn shader(&self, context: &Context) -> Option<ResourceId> {
+        match self {
+            Self::Fade(_) => context.graphics.get_builtin_shader(BuiltInShader::Fade),
+            Self::Wipe(_) => context.graphics.get_builtin_shader(BuiltInShader::Wipe),
+            Self::Custom(shader, _) => Some(*shader),
+        }
+    }
+}
+
+pub struct SceneController<T: Game> {
+    change: Option<SceneChange<T>>,
+    transition: Option<Transition>,
+}
 impl<T: Game> SceneController<T> {
     fn new() -> Self {
-        Self(None)
+        Self {
+            change: None,
+            transition: None,
+        }
     }
     pub fn pop(&mut self) {
-        self.0 = Some(SceneChange::Pop);
+        self.change = Some(SceneChange::Pop);
     }
     pub fn push(&mut self, scene: Box<dyn Scene<Game = T>>) {
-        self.0 = Some(SceneChange::Push(scene));
+        self.change = Some(SceneChange::Push(scene));
     }
     pub fn switch(&mut self, scene: Box<dyn Scene<Game = T>>) {
-        self.0 = Some(SceneChange::Switch(scene));
+        self.change = Some(SceneChange::Switch(scene));
+    }
+    pub fn pop_with(&mut self, transition: Transition) {
+        self.pop();
+        self.transition = Some(transition);
+    }
+    pub fn push_with(&mut self, scene: Box<dyn Scene<Game = T>>, transition: Transition) {
+        self.push(scene);
+        self.transition = Some(transition);
+    }
+    pub fn switch_with(&mut self, scene: Box<dyn Scene<Game = T>>, transition: Transition) {
+        self.switch(scene);
+        self.transition = Some(transition);
     }
 }
 
+struct ActiveTransition<T> {
+    transition: Transition,
+    elapsed: f32,
+    // number of scenes at the bottom of the stack that were
+    // present before the change
+    base: usize,
+    // outgoing scenes already removed from the stack
+    removed: Vec<Box<dyn Scene<Game = T>>>,
+}
+
 pub struct SceneManager<T> {
     scenes: Vec<Box<dyn Scene<Game = T>>>,
     initialized: bool,
+    transition: Option<ActiveTransition<T>>,
 }
 impl<T> SceneManager<T> {
     pub fn new(scene: Box<dyn Scene<Game = T>>) -> Self {
         Self {
             scenes: vec![scene],
             initialized: false,
+            transition: None,
         }
     }
 }
@@ -51,6 +114,14 @@ impl<T: Game> SceneManager<T> {
     pub(crate) fn current_mut(&mut self) -> &mut Box<dyn Scene<Game = T>> {
         self.scenes.last_mut().expect(EMPTY_STACK_MSG)
     }
+    /// Returns the index of the topmost opaque scene,
+    /// where the drawing should start.
+    fn draw_start(&self) -> usize {
+        self.scenes
+            .iter()
+            .rposition(|s| !s.is_overlay())
+            .unwrap_or(0)
+    }
     pub(crate) fn initialize(
         &mut self,
         game: &mut T,
@@ -60,6 +131,29 @@ impl<T: Game> SceneManager<T> {
         self.initialized = true;
         self.current_mut().enter(game, context, controller);
     }
+    fn start_transition(&mut self, transition: Transition) {
+        if self.transition.is_some() {
+            return;
+        }
+        self.transition = Some(ActiveTransition {
+            transition,
+            elapsed: 0.,
+            base: self.scenes.len(),
+            removed: Vec::new(),
+        });
+    }
+    /// Handles a scene removed from the stack `index`.
+    /// It is kept alive while it's still a part of the outgoing frame
+    /// of the transition.
+    fn discard(&mut self, index: usize, scene: Box<dyn Scene<Game = T>>) {
+        let Some(transition) = self.transition.as_mut() else {
+            return;
+        };
+        if index < transition.base {
+            transition.base = index;
+            transition.removed.insert(0, scene);
+        }
+    }
 }
 
 pub fn update_scenes<T: Game>(
@@ -67,6 +161,10 @@ pub fn update_scenes<T: Game>(
     game: &mut T,
     context: &mut Context,
 ) {
+    if scene_manager.transition.is_some() {
+        return;
+    }
+
     let mut controller = SceneController::new();
 
     match scene_manager.initialized {
@@ -81,10 +179,15 @@ pub fn update_scenes<T: Game>(
         }
     }
 
-    while let Some(change) = controller.0.take() {
+    while let Some(change) = controller.change.take() {
+        if let Some(transition) = controller.transition.take() {
+            scene_manager.start_transition(transition);
+        }
         match change {
             SceneChange::Pop => {
-                scene_manager.pop().exit(game, context, &mut controller);
+                let mut scene = scene_manager.pop();
+                scene.exit(game, context, &mut controller);
+                scene_manager.discard(scene_manager.scenes.len(), scene);
                 scene_manager
                     .current_mut()
                     .restore(game, context, &mut controller);
@@ -97,9 +200,9 @@ pub fn update_scenes<T: Game>(
                 scene_manager.push(scene);
             }
             SceneChange::Switch(new_scene) => {
-                scene_manager
-                    .switch(new_scene)
-                    .exit(game, context, &mut controller);
+                let mut prev = scene_manager.switch(new_scene);
+                prev.exit(game, context, &mut controller);
+                scene_manager.discard(scene_manager.scenes.len() - 1, prev);
                 scene_manager
                     .current_mut()
                     .enter(game, context, &mut controller);
@@ -107,3 +210,219 @@ pub fn update_scenes<T: Game>(
         }
     }
 }
+
+/// Draws the visible part of the scene stack, bottom to top.
+/// During a transition the outgoing stack is drawn first.
+pub fn draw_scenes<T: Game>(scene_manager: &mut SceneManager<T>, game: &T, context: &mut Context) {
+    if !scene_manager.initialized {
+        return;
+    }
+    let alpha = context.time.get_alpha();
+
+    if let Some(transition) = scene_manager.transition.as_mut() {
+        let duration = transition.transition.duration();
+        if let Some(shader) = transition.transition.shader(context) {
+            let progress = match duration > 0. {
+                true => (transition.elapsed / duration).min(1.),
+                false => 1.,
+            };
+            context.graphics.begin_transition(shader, progress);
+
+            let mut outgoing = scene_manager.scenes[..transition.base]
+                .iter_mut()
+                .chain(transition.removed.iter_mut())
+                .collect::<Vec<_>>();
+            let start = outgoing.iter().rposition(|s| !s.is_overlay()).unwrap_or(0);
+            for scene in outgoing[start..].iter_mut() {
+                scene.draw(game, context, alpha);
+            }
+
+            context.graphics.end_transition();
+        }
+        transition.elapsed += context.time.get_frame_delta();
+        if transition.elapsed >= duration {
+            scene_manager.transition = None;
+        }
+    }
+
+    let start = scene_manager.draw_start();
+    for scene in scene_manager.scenes[start..].iter_mut() {
+        scene.draw(game, context, alpha);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        rc::Rc,
+        sync::{Arc, Mutex},
+    };
+    use winit::dpi::PhysicalSize;
+
+    use super::*;
+    use crate::{audio::AudioEngine, input::InputContext, time::Time, wgpu::WgpuContext};
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    struct TestGame;
+    impl Game for TestGame {
+        fn setup(&mut self, _context: &mut Context) {}
+    }
+
+    struct TestScene {
+        name: &'static str,
+        overlay: bool,
+        log: Log,
+    }
+    impl TestScene {
+        fn boxed(name: &'static str, overlay: bool, log: &Log) -> Box<Self> {
+            Box::new(Self {
+                name,
+                overlay,
+                log: log.clone(),
+            })
+        }
+    }
+    impl Scene for TestScene {
+        type Game = TestGame;
+        fn update(
+            &mut self,
+            _game: &mut Self::Game,
+            _context: &mut Context,
+            _scenes: &mut SceneController<Self::Game>,
+        ) {
+            self.log.borrow_mut().push(format!("update {}", self.name));
+        }
+        fn draw(&mut self, _game: &Self::Game, _context: &mut Context, _alpha: f32) {
+            self.log.borrow_mut().push(format!("draw {}", self.name));
+        }
+        fn is_overlay(&self) -> bool {
+            self.overlay
+        }
+    }
+
+    struct SwitchScene(Log);
+    impl Scene for SwitchScene {
+        type Game = TestGame;
+        fn update(
+            &mut self,
+            _game: &mut Self::Game,
+            _context: &mut Context,
+            scenes: &mut SceneController<Self::Game>,
+        ) {
+            self.0.borrow_mut().push("update s".to_string());
+            scenes.switch_with(TestScene::boxed("b", true, &self.0), Transition::Fade(1.5));
+        }
+        fn draw(&mut self, _game: &Self::Game, _context: &mut Context, _alpha: f32) {
+            self.0.borrow_mut().push("draw s".to_string());
+        }
+        fn is_overlay(&self) -> bool {
+            true
+        }
+    }
+
+    fn context() -> Context {
+        let assets = Arc::new(Mutex::new(rogalik_assets::AssetStore::default()));
+        Context {
+            audio: AudioEngine::new(assets.clone(), None),
+            graphics: WgpuContext::new(assets.clone()),
+            assets,
+            inner_size: PhysicalSize::default(),
+            input: InputContext::new(),
+            os_path: None,
+            scale_factor: 1.,
+            time: Time::new(),
+        }
+    }
+
+    fn frame(
+        manager: &mut SceneManager<TestGame>,
+        context: &mut Context,
+        log: &Log,
+    ) -> Vec<String> {
+        log.borrow_mut().clear();
+        update_scenes(manager, &mut TestGame, context);
+        draw_scenes(manager, &TestGame, context);
+        log.take()
+    }
+
+    #[test]
+    fn draw_from_topmost_opaque() {
+        let log = Log::default();
+        let mut context = context();
+        let mut manager = SceneManager::new(TestScene::boxed("a", false, &log));
+        manager.initialize(&mut TestGame, &mut context, &mut SceneController::new());
+        manager.push(TestScene::boxed("b", false, &log));
+        manager.push(TestScene::boxed("c", true, &log));
+        manager.push(TestScene::boxed("d", true, &log));
+
+        assert_eq!(
+            frame(&mut manager, &mut context, &log),
+            ["update d", "draw b", "draw c", "draw d"]
+        );
+    }
+
+    #[test]
+    fn opaque_top_hides_the_stack() {
+        let log = Log::default();
+        let mut context = context();
+        let mut manager = SceneManager::new(TestScene::boxed("a", false, &log));
+        manager.initialize(&mut TestGame, &mut context, &mut SceneController::new());
+        manager.push(TestScene::boxed("b", true, &log));
+        manager.push(TestScene::boxed("c", false, &log));
+
+        assert_eq!(
+            frame(&mut manager, &mut context, &log),
+            ["update c", "draw c"]
+        );
+    }
+
+    #[test]
+    fn overlay_only_stack() {
+        let log = Log::default();
+        let mut context = context();
+        let mut manager = SceneManager::new(TestScene::boxed("a", true, &log));
+        manager.initialize(&mut TestGame, &mut context, &mut SceneController::new());
+        manager.push(TestScene::boxed("b", true, &log));
+
+        assert_eq!(
+            frame(&mut manager, &mut context, &log),
+            ["update b", "draw a", "draw b"]
+        );
+    }
+
+    #[test]
+    fn no_draw_before_initialization() {
+        let log = Log::default();
+        let mut context = context();
+        let mut manager = SceneManager::new(TestScene::boxed("a", false, &log));
+        draw_scenes(&mut manager, &TestGame, &mut context);
+        assert!(log.borrow().is_empty());
+    }
+
+    #[test]
+    fn transition_draws_both_scenes() {
+        let log = Log::default();
+        let mut context = context();
+        let mut manager = SceneManager::new(TestScene::boxed("a", false, &log));
+        manager.initialize(&mut TestGame, &mut context, &mut SceneController::new());
+        manager.push(TestScene::boxed("o", true, &log));
+        manager.push(Box::new(SwitchScene(log.clone())));
+        // frame delta defaults to 1s before the first time update
+
+        assert_eq!(
+            frame(&mut manager, &mut context, &log),
+            ["update s", "draw a", "draw o", "draw s", "draw a", "draw o", "draw b"]
+        );
+        // updates are paused during the transition
+        assert_eq!(
+            frame(&mut manager, &mut context, &log),
+            ["draw a", "draw o", "draw s", "draw a", "draw o", "draw b"]
+        );
+        assert_eq!(
+            frame(&mut manager, &mut context, &log),
+            ["update b", "draw a", "draw o", "draw b"]
+        );
+    }
+}
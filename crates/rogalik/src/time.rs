@@ -5,6 +5,10 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use rogalik_common::ResourceId;
 
+/// Max number of fixed updates executed in a single frame.
+/// Prevents the spiral of death when the updates can't keep up.
+const MAX_FIXED_STEPS: u32 = 8;
+
 pub struct Time {
     // Game engine start ts
     start: Instant,
@@ -12,6 +16,9 @@ pub struct Time {
     timers: HashMap<ResourceId, Timer>,
     next_timer_id: usize,
     frame_start: Instant,
+    fixed_step: Option<f32>,
+    accumulator: f32,
+    alpha: f32,
 }
 impl Time {
     pub fn new() -> Self {
@@ -21,15 +28,62 @@ impl Time {
             timers: HashMap::default(),
             next_timer_id: 0,
             frame_start: Instant::now(),
+            fixed_step: None,
+            accumulator: 0.,
+            alpha: 1.,
+        }
+    }
+    /// Creates a time instance running the updates at a fixed rate.
+    pub fn with_fixed_step(step: f32) -> Self {
+        Self {
+            fixed_step: Some(step),
+            accumulator: 0.,
+            alpha: 0.,
+            ..Self::new()
         }
     }
+    /// Measures the frame duration.
+    /// In the fixed step mode the timers are advanced in `fixed_tick` instead.
     pub fn update(&mut self) {
-        self.delta = self.frame_start.elapsed();
+        self.update_with_delta(self.frame_start.elapsed());
+    }
+    /// Same as `update`, but with a given frame duration instead of
+    /// the measured one (eg. when replaying a recorded input).
+    pub fn update_with_delta(&mut self, delta: f32) {
+        self.delta = delta;
         self.frame_start = Instant::now();
-        for timer in self.timers.values_mut() {
-            timer.update(self.delta);
+        match self.fixed_step {
+            Some(_) => self.accumulator += self.delta,
+            None => self.update_timers(self.delta),
+        }
+    }
+    /// Returns the number of fixed updates that should be executed
+    /// in the current frame and recalculates the interpolation alpha.
+    /// Always 1 when the fixed step mode is not enabled.
+    pub(crate) fn take_fixed_steps(&mut self) -> u32 {
+        let Some(step) = self.fixed_step else {
+            return 1;
+        };
+        let mut steps = (self.accumulator / step) as u32;
+        if steps > MAX_FIXED_STEPS {
+            // drop the time that can't be caught up with
+            steps = MAX_FIXED_STEPS;
+            self.accumulator = self.accumulator.rem_euclid(step);
+        } else {
+            self.accumulator -= steps as f32 * step;
+        }
+        self.alpha = (self.accumulator / step).clamp(0., 1.);
+        steps
+    }
+    /// Advances the timers by a single fixed step.
+    pub(crate) fn fixed_tick(&mut self) {
+        if let Some(step) = self.fixed_step {
+            self.update_timers(step);
         }
     }
+    pub fn is_fixed_step(&self) -> bool {
+        self.fixed_step.is_some()
+    }
     pub fn add_timer(&mut self, tick: f32) -> ResourceId {
         let timer = Timer::new(tick);
         let id = ResourceId(self.next_timer_id);
@@ -40,9 +94,21 @@ impl Time {
     pub fn remove_timer(&mut self, id: ResourceId) {
         self.timers.remove(&id);
     }
+    /// Returns the time step of the update.
+    /// In the fixed step mode this is always the fixed step value.
     pub fn get_delta(&self) -> f32 {
+        self.fixed_step.unwrap_or(self.delta)
+    }
+    /// Returns the real duration of the last frame.
+    pub fn get_frame_delta(&self) -> f32 {
         self.delta
     }
+    /// Returns the fraction of a fixed step accumulated but not yet simulated.
+    /// Should be used to interpolate between the previous and the current state
+    /// when rendering. Always 1 when the fixed step mode is not enabled.
+    pub fn get_alpha(&self) -> f32 {
+        self.alpha
+    }
     pub fn get_timer(&self, id: ResourceId) -> Option<&Timer> {
         self.timers.get(&id)
     }
@@ -50,6 +116,11 @@ impl Time {
     pub fn elapsed(&self) -> f32 {
         self.start.elapsed()
     }
+    fn update_timers(&mut self, delta: f32) {
+        for timer in self.timers.values_mut() {
+            timer.update(delta);
+        }
+    }
 }
 
 pub struct Timer {
@@ -61,7 +132,7 @@ impl Timer {
     fn new(tick: f32) -> Self {
         Timer {
             state: 0.,
-            tick: tick,
+            tick,
             finished: false,
         }
     }
@@ -124,9 +195,8 @@ where
 {
     let duration = std::time::Duration::deserialize(deserializer)?;
     let now = std::time::Instant::now();
-    Ok(now
-        .checked_sub(duration)
-        .ok_or(serde::de::Error::custom("Invalid instant"))?)
+    now.checked_sub(duration)
+        .ok_or(serde::de::Error::custom("Invalid instant"))
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -138,3 +208,39 @@ where
     let duration = instant.elapsed();
     duration.serialize(serializer)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_steps_accumulate() {
+        let mut time = Time::with_fixed_step(0.25);
+        time.accumulator = 0.6;
+        assert_eq!(time.take_fixed_steps(), 2);
+        assert!((time.get_alpha() - 0.4).abs() < 1e-5);
+        assert_eq!(time.get_delta(), 0.25);
+
+        time.accumulator += 0.2;
+        assert_eq!(time.take_fixed_steps(), 1);
+        assert!((time.get_alpha() - 0.2).abs() < 1e-5);
+
+        time.accumulator += 0.1;
+        assert_eq!(time.take_fixed_steps(), 0);
+    }
+
+    #[test]
+    fn fixed_steps_clamped() {
+        let mut time = Time::with_fixed_step(0.1);
+        time.accumulator = 10.05;
+        assert_eq!(time.take_fixed_steps(), MAX_FIXED_STEPS);
+        assert!(time.accumulator < 0.1);
+    }
+
+    #[test]
+    fn variable_step_single_update() {
+        let mut time = Time::new();
+        assert_eq!(time.take_fixed_steps(), 1);
+        assert_eq!(time.get_alpha(), 1.);
+    }
+}
@@ -48,12 +48,30 @@ pub trait Scene {
         scenes: &mut SceneController<Self::Game>,
     ) {
     }
+    /// Game logic.
+    /// When the fixed update rate is enabled, it can be executed
+    /// zero or more times per frame.
     fn update(
         &mut self,
         game: &mut Self::Game,
         context: &mut Context,
         scenes: &mut SceneController<Self::Game>,
     );
+    /// Triggered once per rendered frame, after the updates.
+    /// `alpha` is the fraction of the fixed step that elapsed since the last
+    /// update (always 1 when the fixed update rate is not enabled) - it can be
+    /// used to interpolate between the previous and the current state.
+    /// In the fixed step mode the time in seconds since the last update is
+    /// `alpha * context.time.get_delta()` (`get_delta` returns the fixed step).
+    #[allow(unused_variables)]
+    fn draw(&mut self, game: &Self::Game, context: &mut Context, alpha: f32) {}
+    /// Overlay scenes (eg. pause menus or inventories) are transparent:
+    /// the scenes below them on the stack remain visible.
+    /// The stack is drawn from the topmost opaque (non-overlay) scene upward,
+    /// while only the top scene is updated.
+    fn is_overlay(&self) -> bool {
+        false
+    }
 }
 
 pub(crate) enum SceneChange<T: Game> {
@@ -36,7 +36,7 @@ impl DevFileStore {
                 continue;
             };
 
-            let Ok(file_meta) = fs::metadata(&meta.path.as_path()) else {
+            let Ok(file_meta) = fs::metadata(meta.path.as_path()) else {
                 continue;
             };
             let Ok(modified) = get_modified_u64(&file_meta) else {
@@ -70,7 +70,7 @@ impl AssetContext for DevFileStore {
         let abs_path = Path::new(&self.root).join(path);
         let data = fs::read(&abs_path).map_err(|_| EngineError::ResourceNotFound)?;
 
-        let meta = fs::metadata(&abs_path.as_path()).map_err(|_| EngineError::ResourceNotFound)?;
+        let meta = fs::metadata(abs_path.as_path()).map_err(|_| EngineError::ResourceNotFound)?;
         let modified = get_modified_u64(&meta)?;
 
         log::debug!("Loaded asset from: {}. {} bytes.", path, data.len());
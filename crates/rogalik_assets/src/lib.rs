@@ -51,6 +51,7 @@ pub enum AssetState {
 }
 
 pub trait AssetContext: Default {
+    #[allow(clippy::wrong_self_convention)]
     fn from_bytes(&mut self, data: &'static [u8]) -> ResourceId;
     fn load(&mut self, path: &str) -> Result<ResourceId, EngineError>;
     fn get(&self, asset_id: ResourceId) -> Option<&Asset>;
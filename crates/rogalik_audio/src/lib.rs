@@ -55,16 +55,16 @@ impl AudioSetup for AudioEngine {
                         let master_volume = state.volume;
 
                         for data_samples in data.chunks_mut(CHANNEL_COUNT) {
-                            // Should get unrolled by the compiler
-                            for i in 0..CHANNEL_COUNT {
-                                data_samples[i] = 0.;
-                            }
+                            data_samples.fill(0.);
 
                             for source in state.assets.sources.iter_mut().filter(|s| s.is_playing())
                             {
                                 let source_samples = source.next();
-                                for i in 0..CHANNEL_COUNT {
-                                    data_samples[i] += master_volume * source_samples[i];
+                                // Should get unrolled by the compiler
+                                for (sample, source_sample) in
+                                    data_samples.iter_mut().zip(source_samples)
+                                {
+                                    *sample += master_volume * source_sample;
                                 }
                             }
                         }
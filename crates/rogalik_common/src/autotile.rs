@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+
+use rogalik_math::vectors::Vector2i;
+
+use crate::structs::{EngineError, ResourceId};
+use crate::traits::GraphicsContext;
+
+// neighbour bits of the masks (the y axis pointing up)
+pub const MASK_N: u8 = 1;
+pub const MASK_NE: u8 = 2;
+pub const MASK_E: u8 = 4;
+pub const MASK_SE: u8 = 8;
+pub const MASK_S: u8 = 16;
+pub const MASK_SW: u8 = 32;
+pub const MASK_W: u8 = 64;
+pub const MASK_NW: u8 = 128;
+
+const NEIGHBOURS: [(u8, Vector2i); 8] = [
+    (MASK_N, Vector2i::new(0, 1)),
+    (MASK_NE, Vector2i::new(1, 1)),
+    (MASK_E, Vector2i::new(1, 0)),
+    (MASK_SE, Vector2i::new(1, -1)),
+    (MASK_S, Vector2i::new(0, -1)),
+    (MASK_SW, Vector2i::new(-1, -1)),
+    (MASK_W, Vector2i::new(-1, 0)),
+    (MASK_NW, Vector2i::new(-1, 1)),
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum AutotileMode {
+    /// Edge neighbours only (N, E, S, W) - 16 tile variants.
+    /// The mask bits are compressed to: N = 1, E = 2, S = 4, W = 8.
+    #[default]
+    Bitmask4,
+    /// All eight neighbours, with the corners counted only if both adjacent
+    /// edges are connected - 47 tile variants (the "blob" set).
+    Blob8,
+}
+impl AutotileMode {
+    /// Number of the tile variants.
+    pub fn variants(&self) -> usize {
+        match self {
+            Self::Bitmask4 => 16,
+            Self::Blob8 => 47,
+        }
+    }
+}
+
+/// Tile selection for a single terrain type.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct AutotileRule {
+    pub terrain: u32,
+    pub mode: AutotileMode,
+    /// Atlas index of the first variant. If the atlas follows the
+    /// ascending mask order, the tiles are selected without any explicit
+    /// mapping.
+    pub first: usize,
+    /// Atlas indices of the masks, overriding the `first` based ones.
+    /// Masks use the `MASK_*` bits (compressed for the `Bitmask4` mode).
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub tiles: HashMap<u8, usize>,
+    /// Other terrains the rule connects to (eg. doors within the walls).
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub connects_to: Vec<u32>,
+    /// Treat the tiles outside of the map as connected.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub connect_edges: bool,
+}
+impl AutotileRule {
+    pub fn new(terrain: u32, mode: AutotileMode, first: usize) -> Self {
+        Self {
+            terrain,
+            mode,
+            first,
+            ..Default::default()
+        }
+    }
+    /// Returns the neighbour mask of the tile at `v`.
+    /// `terrain` returns the terrain type at the given position, or `None`
+    /// outside of the map.
+    pub fn get_mask(&self, v: Vector2i, terrain: impl Fn(Vector2i) -> Option<u32>) -> u8 {
+        let mut mask = 0;
+        for (bit, offset) in NEIGHBOURS {
+            let connected = match terrain(v + offset) {
+                Some(t) => t == self.terrain || self.connects_to.contains(&t),
+                None => self.connect_edges,
+            };
+            if connected {
+                mask |= bit;
+            }
+        }
+        match self.mode {
+            AutotileMode::Bitmask4 => compress_edges(mask),
+            AutotileMode::Blob8 => reduce_blob(mask),
+        }
+    }
+    /// Returns the atlas index for the mask.
+    pub fn get_tile(&self, mask: u8) -> usize {
+        if let Some(&idx) = self.tiles.get(&mask) {
+            return idx;
+        }
+        match self.mode {
+            AutotileMode::Bitmask4 => self.first + mask as usize,
+            AutotileMode::Blob8 => self.first + (0..mask).filter(|&m| reduce_blob(m) == m).count(),
+        }
+    }
+}
+
+/// Set of the autotile rules, picking the atlas indices from the neighbour
+/// masks. Can be loaded from a data file (with the `serialize` feature).
+/// Terrain types without a rule are left empty.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct AutotileRules {
+    pub rules: Vec<AutotileRule>,
+}
+impl AutotileRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_rule(mut self, rule: AutotileRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+    pub fn get_rule(&self, terrain: u32) -> Option<&AutotileRule> {
+        self.rules.iter().find(|r| r.terrain == terrain)
+    }
+    /// Returns the atlas index of the tile at `v`.
+    pub fn get_tile(
+        &self,
+        v: Vector2i,
+        terrain: impl Fn(Vector2i) -> Option<u32>,
+    ) -> Option<usize> {
+        let rule = self.get_rule(terrain(v)?)?;
+        Some(rule.get_tile(rule.get_mask(v, terrain)))
+    }
+    /// Sets the tiles of the whole tilemap layer (of the `w` x `h` size).
+    pub fn apply(
+        &self,
+        graphics: &mut impl GraphicsContext,
+        tilemap: ResourceId,
+        layer: usize,
+        w: u32,
+        h: u32,
+        terrain: impl Fn(Vector2i) -> Option<u32>,
+    ) -> Result<(), EngineError> {
+        for y in 0..h as i32 {
+            for x in 0..w as i32 {
+                let v = Vector2i::new(x, y);
+                graphics.set_tile(tilemap, layer, v, self.get_tile(v, &terrain))?;
+            }
+        }
+        Ok(())
+    }
+    /// Updates the tile at `v` and its neighbours, after the terrain change.
+    /// Neighbours outside of the map are skipped.
+    pub fn update(
+        &self,
+        graphics: &mut impl GraphicsContext,
+        tilemap: ResourceId,
+        layer: usize,
+        v: Vector2i,
+        terrain: impl Fn(Vector2i) -> Option<u32>,
+    ) -> Result<(), EngineError> {
+        for offset in std::iter::once(Vector2i::ZERO).chain(NEIGHBOURS.map(|(_, o)| o)) {
+            let n = v + offset;
+            if terrain(n).is_none() {
+                continue;
+            }
+            graphics.set_tile(tilemap, layer, n, self.get_tile(n, &terrain))?;
+        }
+        Ok(())
+    }
+}
+
+// N, E, S, W bits into the 0-15 range
+fn compress_edges(mask: u8) -> u8 {
+    [MASK_N, MASK_E, MASK_S, MASK_W]
+        .iter()
+        .enumerate()
+        .filter(|(_, &bit)| mask & bit != 0)
+        .fold(0, |acc, (i, _)| acc | 1 << i)
+}
+
+// corners are relevant only next to the two connected edges
+fn reduce_blob(mask: u8) -> u8 {
+    let mut mask = mask;
+    for (corner, a, b) in [
+        (MASK_NE, MASK_N, MASK_E),
+        (MASK_SE, MASK_S, MASK_E),
+        (MASK_SW, MASK_S, MASK_W),
+        (MASK_NW, MASK_N, MASK_W),
+    ] {
+        if mask & a == 0 || mask & b == 0 {
+            mask &= !corner;
+        }
+    }
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 1 - wall, 0 - floor
+    const MAP: [&str; 4] = ["0110", "0111", "0111", "0000"];
+
+    fn terrain(v: Vector2i) -> Option<u32> {
+        if v.x < 0 || v.y < 0 || v.x > 3 || v.y > 3 {
+            return None;
+        }
+        // the first row is at the top
+        let row = MAP[3 - v.y as usize];
+        Some(row.as_bytes()[v.x as usize] as u32 - b'0' as u32)
+    }
+
+    #[test]
+    fn bitmask4() {
+        let rule = AutotileRule::new(1, AutotileMode::Bitmask4, 100);
+        // N, E, S
+        assert_eq!(rule.get_mask(Vector2i::new(1, 2), terrain), 0b0111);
+        assert_eq!(rule.get_tile(0b0111), 107);
+        // outside of the map
+        assert_eq!(rule.get_mask(Vector2i::new(2, 3), terrain), 0b1100);
+        let rule = AutotileRule {
+            connect_edges: true,
+            ..rule
+        };
+        assert_eq!(rule.get_mask(Vector2i::new(2, 3), terrain), 0b1101);
+    }
+
+    #[test]
+    fn blob8() {
+        let mut rule = AutotileRule::new(1, AutotileMode::Blob8, 0);
+        let masks = (0..=255u8).filter(|&m| reduce_blob(m) == m).count();
+        assert_eq!(masks, AutotileMode::Blob8.variants());
+
+        // the SE corner is dropped, as E is not connected
+        assert_eq!(
+            rule.get_mask(Vector2i::new(2, 3), terrain),
+            MASK_S | MASK_SW | MASK_W
+        );
+        // last variant - all the neighbours
+        assert_eq!(rule.get_tile(255), 46);
+
+        rule.tiles.insert(255, 3);
+        assert_eq!(rule.get_tile(255), 3);
+    }
+
+    #[test]
+    fn rule_set() {
+        let rules = AutotileRules::new()
+            .with_rule(AutotileRule::new(1, AutotileMode::Bitmask4, 16))
+            .with_rule(AutotileRule {
+                connects_to: vec![1],
+                ..AutotileRule::new(0, AutotileMode::Bitmask4, 0)
+            });
+        assert_eq!(
+            rules.get_tile(Vector2i::new(3, 2), terrain),
+            Some(16 + 0b1100)
+        );
+        // floor connected to the walls
+        assert_eq!(rules.get_tile(Vector2i::new(0, 0), terrain), Some(0b0011));
+        assert_eq!(rules.get_tile(Vector2i::new(4, 0), terrain), None);
+    }
+}
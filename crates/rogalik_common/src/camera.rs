@@ -0,0 +1,251 @@
+use rogalik_math::vectors::Vector2f;
+
+/// Parameters of the smoothed camera follow.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraFollowParams {
+    /// Exponential smoothing rate (per second). The higher the value the
+    /// faster the camera catches up. 0. moves the camera instantly.
+    pub speed: f32,
+    /// Half size (in world units) of the area around the camera's target,
+    /// in which the followed point can move without moving the camera.
+    pub deadzone: Vector2f,
+}
+impl Default for CameraFollowParams {
+    fn default() -> Self {
+        Self {
+            speed: 5.,
+            deadzone: Vector2f::ZERO,
+        }
+    }
+}
+
+/// Parameters of the trauma based screen shake.
+/// The shake offset is proportional to the trauma squared.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraShakeParams {
+    /// Max offset (in world units) at the full trauma.
+    pub max_offset: f32,
+    /// Trauma decrease per second.
+    pub decay: f32,
+    /// Speed of the shake oscillations.
+    pub frequency: f32,
+}
+impl Default for CameraShakeParams {
+    fn default() -> Self {
+        Self {
+            max_offset: 8.,
+            decay: 1.5,
+            frequency: 25.,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct ZoomTween {
+    from: f32,
+    to: f32,
+    duration: f32,
+    elapsed: f32,
+}
+
+/// Follow, bounds, shake and zoom logic, shared by the camera
+/// implementations. Driven by the frame delta through `Camera::update`.
+#[derive(Clone, Debug, Default)]
+pub struct CameraController {
+    pub follow_params: CameraFollowParams,
+    pub shake_params: CameraShakeParams,
+    follow_target: Option<Vector2f>,
+    world_bounds: Option<(Vector2f, Vector2f)>,
+    trauma: f32,
+    shake_time: f32,
+    shake_offset: Vector2f,
+    zoom: Option<ZoomTween>,
+}
+impl CameraController {
+    pub fn follow(&mut self, target: Vector2f) {
+        self.follow_target = Some(target);
+    }
+    pub fn stop_follow(&mut self) {
+        self.follow_target = None;
+    }
+    pub fn get_follow_target(&self) -> Option<Vector2f> {
+        self.follow_target
+    }
+    pub fn set_world_bounds(&mut self, bounds: Option<(Vector2f, Vector2f)>) {
+        self.world_bounds = bounds;
+    }
+    pub fn get_world_bounds(&self) -> Option<(Vector2f, Vector2f)> {
+        self.world_bounds
+    }
+    /// Adds trauma (clamped to the 0. - 1. range).
+    pub fn shake(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).clamp(0., 1.);
+    }
+    pub fn get_trauma(&self) -> f32 {
+        self.trauma
+    }
+    /// Current shake offset, to be applied to the rendered view only.
+    pub fn get_shake_offset(&self) -> Vector2f {
+        self.shake_offset
+    }
+    pub fn zoom_to(&mut self, from: f32, to: f32, duration: f32) {
+        self.zoom = Some(ZoomTween {
+            from,
+            to,
+            duration,
+            elapsed: 0.,
+        });
+    }
+    pub fn stop_zoom(&mut self) {
+        self.zoom = None;
+    }
+    pub fn is_zooming(&self) -> bool {
+        self.zoom.is_some()
+    }
+    /// Keeps the view (of the `half_view` size) within the world bounds.
+    /// If the bounds are smaller than the view, the view is centered on them.
+    pub fn clamp_target(&self, target: Vector2f, half_view: Vector2f) -> Vector2f {
+        let Some((min, max)) = self.world_bounds else {
+            return target;
+        };
+        Vector2f::new(
+            clamp_axis(target.x, min.x, max.x, half_view.x),
+            clamp_axis(target.y, min.y, max.y, half_view.y),
+        )
+    }
+    /// Advances the controller by the frame `delta`.
+    /// `view_size` is the size of the view at the scale of 1.
+    pub fn update(
+        &mut self,
+        delta: f32,
+        target: &mut Vector2f,
+        scale: &mut f32,
+        view_size: Vector2f,
+    ) {
+        if let Some(zoom) = self.zoom.as_mut() {
+            zoom.elapsed += delta;
+            let t = match zoom.duration > 0. {
+                true => (zoom.elapsed / zoom.duration).min(1.),
+                false => 1.,
+            };
+            // smoothstep easing
+            let t = t * t * (3. - 2. * t);
+            *scale = zoom.from + (zoom.to - zoom.from) * t;
+            if zoom.elapsed >= zoom.duration {
+                self.zoom = None;
+            }
+        }
+
+        if let Some(follow) = self.follow_target {
+            let deadzone = self.follow_params.deadzone;
+            let desired = Vector2f::new(
+                apply_deadzone(target.x, follow.x, deadzone.x),
+                apply_deadzone(target.y, follow.y, deadzone.y),
+            );
+            *target = match self.follow_params.speed > 0. {
+                true => {
+                    let t = 1. - (-self.follow_params.speed * delta).exp();
+                    *target + (desired - *target) * t
+                }
+                false => desired,
+            };
+        }
+
+        *target = self.clamp_target(*target, 0.5 * view_size / *scale);
+
+        self.trauma = (self.trauma - self.shake_params.decay * delta).max(0.);
+        self.shake_time += delta;
+        self.shake_offset = match self.trauma > 0. {
+            true => {
+                let t = self.shake_time * self.shake_params.frequency;
+                let amount = self.shake_params.max_offset * self.trauma * self.trauma;
+                // cheap deterministic noise - sines of incommensurable frequencies
+                amount
+                    * Vector2f::new(
+                        0.5 * ((1.13 * t).sin() + (2.71 * t + 1.3).sin()),
+                        0.5 * ((1.37 * t + 0.7).sin() + (3.07 * t).sin()),
+                    )
+            }
+            false => Vector2f::ZERO,
+        };
+    }
+}
+
+fn apply_deadzone(current: f32, target: f32, deadzone: f32) -> f32 {
+    let offset = target - current;
+    if offset.abs() <= deadzone {
+        return current;
+    }
+    target - deadzone * offset.signum()
+}
+
+fn clamp_axis(v: f32, min: f32, max: f32, half_view: f32) -> f32 {
+    if max - min <= 2. * half_view {
+        return 0.5 * (min + max);
+    }
+    v.clamp(min + half_view, max - half_view)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follow_with_deadzone() {
+        let mut controller = CameraController {
+            follow_params: CameraFollowParams {
+                speed: 0.,
+                deadzone: Vector2f::new(10., 10.),
+            },
+            ..Default::default()
+        };
+        let mut target = Vector2f::ZERO;
+        let mut scale = 1.;
+
+        controller.follow(Vector2f::new(5., -5.));
+        controller.update(0.1, &mut target, &mut scale, Vector2f::splat(100.));
+        assert_eq!(target, Vector2f::ZERO);
+
+        controller.follow(Vector2f::new(25., -15.));
+        controller.update(0.1, &mut target, &mut scale, Vector2f::splat(100.));
+        assert_eq!(target, Vector2f::new(15., -5.));
+
+        // smoothed
+        controller.follow_params = CameraFollowParams::default();
+        controller.follow(Vector2f::new(115., -5.));
+        controller.update(0.1, &mut target, &mut scale, Vector2f::splat(100.));
+        assert!(target.x > 15. && target.x < 115.);
+    }
+
+    #[test]
+    fn world_bounds() {
+        let mut controller = CameraController::default();
+        controller.set_world_bounds(Some((Vector2f::ZERO, Vector2f::new(200., 50.))));
+        let mut target = Vector2f::new(-100., 100.);
+        let mut scale = 1.;
+        controller.update(0.1, &mut target, &mut scale, Vector2f::splat(100.));
+        // x clamped, y centered as the bounds are smaller than the view
+        assert_eq!(target, Vector2f::new(50., 25.));
+    }
+
+    #[test]
+    fn shake_decay_and_zoom() {
+        let mut controller = CameraController::default();
+        let mut target = Vector2f::ZERO;
+        let mut scale = 1.;
+        controller.shake(2.);
+        assert_eq!(controller.get_trauma(), 1.);
+        controller.zoom_to(1., 2., 1.);
+
+        controller.update(0.5, &mut target, &mut scale, Vector2f::splat(100.));
+        assert_eq!(scale, 1.5);
+        assert!(controller.get_shake_offset() != Vector2f::ZERO);
+        // the shake does not move the target itself
+        assert_eq!(target, Vector2f::ZERO);
+
+        controller.update(0.5, &mut target, &mut scale, Vector2f::splat(100.));
+        assert_eq!(scale, 2.);
+        assert!(!controller.is_zooming());
+        assert_eq!(controller.get_shake_offset(), Vector2f::ZERO);
+    }
+}
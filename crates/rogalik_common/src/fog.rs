@@ -0,0 +1,201 @@
+use std::collections::HashSet;
+
+use rogalik_math::vectors::{Vector2f, Vector2i};
+
+use crate::structs::{
+    BuiltInShader, Color, EngineError, MaterialParams, ResourceId, SpriteParams, TextureFiltering,
+};
+use crate::traits::GraphicsContext;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Visibility {
+    #[default]
+    Hidden,
+    /// Seen before, but not visible currently.
+    Explored,
+    Visible,
+}
+
+/// Fog of war over a tile grid.
+/// The visibility states are uploaded as a small texture (a pixel per tile),
+/// which is stretched over the grid with a linear filtering and blended over
+/// the world by the built-in fog shader - giving the soft edges.
+/// Tiles outside of the grid are hidden.
+pub struct FogOfWar {
+    /// Fog color, its alpha is the opacity over the hidden tiles.
+    pub color: Color,
+    /// Opacity over the explored tiles, relative to the hidden ones.
+    pub explored_opacity: f32,
+    width: u32,
+    height: u32,
+    // grid position of the bottom-left tile
+    origin: Vector2i,
+    tile_size: f32,
+    states: Vec<Visibility>,
+    material: Option<String>,
+    texture: Option<ResourceId>,
+    // the texture needs to be updated
+    changed: bool,
+}
+impl FogOfWar {
+    /// `tile_size`: size of the tile in world units.
+    pub fn new(width: u32, height: u32, tile_size: f32) -> Self {
+        Self {
+            color: Color(0, 0, 0, 255),
+            explored_opacity: 0.6,
+            width,
+            height,
+            origin: Vector2i::ZERO,
+            tile_size,
+            states: vec![Visibility::Hidden; (width * height) as usize],
+            material: None,
+            texture: None,
+            changed: true,
+        }
+    }
+    /// Sets the grid position of the bottom-left tile.
+    pub fn with_origin(mut self, origin: Vector2i) -> Self {
+        self.origin = origin;
+        self
+    }
+    pub fn get(&self, v: Vector2i) -> Visibility {
+        match self.get_index(v) {
+            Some(idx) => self.states[idx],
+            None => Visibility::Hidden,
+        }
+    }
+    pub fn set(&mut self, v: Vector2i, visibility: Visibility) {
+        if let Some(idx) = self.get_index(v) {
+            self.changed |= self.states[idx] != visibility;
+            self.states[idx] = visibility;
+        }
+    }
+    /// Marks the tiles as visible (eg. the result of
+    /// `rogalik_math::vectors::visible_tiles`). Tiles that are not visible
+    /// anymore become explored.
+    pub fn update_visible(&mut self, visible: &HashSet<Vector2i>) {
+        for state in self.states.iter_mut() {
+            if *state == Visibility::Visible {
+                *state = Visibility::Explored;
+            }
+        }
+        for v in visible {
+            if let Some(idx) = self.get_index(*v) {
+                self.states[idx] = Visibility::Visible;
+            }
+        }
+        self.changed = true;
+    }
+    /// Marks all the tiles as explored.
+    pub fn reveal_all(&mut self) {
+        self.states.fill(Visibility::Explored);
+        self.changed = true;
+    }
+    /// Marks all the tiles as hidden.
+    pub fn reset(&mut self) {
+        self.states.fill(Visibility::Hidden);
+        self.changed = true;
+    }
+    /// Creates the fog texture and the `name` material.
+    /// Should be called once, with the other assets.
+    pub fn create(
+        &mut self,
+        graphics: &mut impl GraphicsContext,
+        name: &str,
+    ) -> Result<(), EngineError> {
+        let texture = graphics.create_texture(self.width, self.height, &self.get_pixels())?;
+        graphics.load_material(
+            name,
+            MaterialParams {
+                diffuse_texture: Some(texture),
+                shader: graphics.get_builtin_shader(BuiltInShader::Fog),
+                filtering: TextureFiltering::Linear,
+                ..Default::default()
+            },
+        );
+        self.texture = Some(texture);
+        self.material = Some(name.to_string());
+        self.changed = false;
+        Ok(())
+    }
+    /// Uploads the changed states and queues the fog for drawing over the
+    /// grid area.
+    pub fn draw(
+        &mut self,
+        graphics: &mut impl GraphicsContext,
+        z_index: i32,
+    ) -> Result<(), EngineError> {
+        let (Some(material), Some(texture)) = (&self.material, self.texture) else {
+            return Err(EngineError::GraphicsNotReady);
+        };
+        if self.changed {
+            graphics.update_texture(
+                texture,
+                (0, 0, self.width, self.height),
+                &self.get_pixels(),
+            )?;
+            self.changed = false;
+        }
+        let origin = Vector2f::new(self.origin.x as f32, self.origin.y as f32) * self.tile_size;
+        let size = Vector2f::new(self.width as f32, self.height as f32) * self.tile_size;
+        graphics.draw_sprite(
+            material,
+            origin,
+            z_index,
+            size,
+            SpriteParams {
+                color: self.color,
+                ..Default::default()
+            },
+        )
+    }
+    fn get_index(&self, v: Vector2i) -> Option<usize> {
+        let local = v - self.origin;
+        if local.x < 0
+            || local.y < 0
+            || local.x >= self.width as i32
+            || local.y >= self.height as i32
+        {
+            return None;
+        }
+        Some((local.y as u32 * self.width + local.x as u32) as usize)
+    }
+    // fog opacity in the alpha channel, rows from top to bottom
+    fn get_pixels(&self) -> Vec<u8> {
+        let explored = (255. * self.explored_opacity.clamp(0., 1.)) as u8;
+        let mut pixels = Vec::with_capacity(4 * self.states.len());
+        for row in self.states.chunks(self.width as usize).rev() {
+            for state in row {
+                let alpha = match state {
+                    Visibility::Hidden => 255,
+                    Visibility::Explored => explored,
+                    Visibility::Visible => 0,
+                };
+                pixels.extend([255, 255, 255, alpha]);
+            }
+        }
+        pixels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visibility_states() {
+        let mut fog = FogOfWar::new(3, 2, 1.).with_origin(Vector2i::new(-1, 0));
+        fog.update_visible(&HashSet::from([Vector2i::new(-1, 0), Vector2i::new(5, 5)]));
+        assert_eq!(fog.get(Vector2i::new(-1, 0)), Visibility::Visible);
+        assert_eq!(fog.get(Vector2i::new(0, 0)), Visibility::Hidden);
+        assert_eq!(fog.get(Vector2i::new(5, 5)), Visibility::Hidden);
+
+        fog.update_visible(&HashSet::from([Vector2i::new(1, 1)]));
+        assert_eq!(fog.get(Vector2i::new(-1, 0)), Visibility::Explored);
+        assert_eq!(fog.get(Vector2i::new(1, 1)), Visibility::Visible);
+
+        // top row first
+        let alpha = fog.get_pixels().chunks(4).map(|p| p[3]).collect::<Vec<_>>();
+        assert_eq!(alpha, [255, 255, 0, 153, 255, 255]);
+    }
+}
@@ -1,8 +1,15 @@
+pub mod autotile;
+pub mod camera;
+pub mod fog;
 pub mod structs;
 pub mod traits;
 
+pub use autotile::{AutotileMode, AutotileRule, AutotileRules};
+pub use camera::{CameraController, CameraFollowParams, CameraShakeParams};
+pub use fog::{FogOfWar, Visibility};
 pub use structs::{
-    AtlasParams, AudioDeviceParams, BuiltInShader, Color, EngineError, MaterialParams,
+    AtlasParams, AudioDeviceParams, BuiltInShader, Color, EngineError, LayerParams, MaterialParams,
     PostProcessParams, ResourceId, ShaderKind, SpriteParams, TextureFiltering, TextureRepeat,
+    TilemapParams, UpscaleMode, DEFAULT_LAYER,
 };
 pub use traits::{AudioContext, Camera, GraphicsContext};
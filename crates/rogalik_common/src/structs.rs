@@ -91,11 +91,64 @@ pub struct PostProcessParams {
     pub filtering: TextureFiltering,
 }
 
+/// Name of the layer, that is always present and drawn first.
+/// It is upscaled (if the rendering resolution is set) and processed by all
+/// the post processes that are not assigned to other layers.
+pub const DEFAULT_LAYER: &str = "default";
+
+#[derive(Clone, Debug, Default)]
+pub struct LayerParams {
+    /// Camera selected when switching to the layer.
+    pub camera: Option<ResourceId>,
+    /// Render the layer at the rendering resolution and upscale it
+    /// (as the default layer). Otherwise the layer is rendered at the native
+    /// window resolution.
+    pub upscale: bool,
+    /// Names of the post processes applied to the layer, in order.
+    pub post_process: Vec<String>,
+}
+
+/// Parameters of a chunked tilemap.
+#[derive(Clone, Debug)]
+pub struct TilemapParams {
+    /// Width of the map, in tiles.
+    pub width: u32,
+    /// Height of the map, in tiles.
+    pub height: u32,
+    /// Size of a tile in world units.
+    pub tile_size: Vector2f,
+    /// World position of the map's bottom-left corner.
+    pub origin: Vector2f,
+    /// Z-indices of the map layers (a layer is created for each value).
+    pub layers: Vec<i32>,
+    /// Width and height of a chunk, in tiles (max 128).
+    /// Chunks are the units of the GPU buffer rebuilds and camera culling.
+    pub chunk_size: u32,
+}
+impl Default for TilemapParams {
+    fn default() -> Self {
+        Self {
+            width: 0,
+            height: 0,
+            tile_size: Vector2f::new(1., 1.),
+            origin: Vector2f::ZERO,
+            layers: vec![0],
+            chunk_size: 16,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct AtlasParams {
     pub cols: usize,
     pub rows: usize,
+    /// Space between the sprites. Without a `margin` it is also left after
+    /// the last column and row (each cell is `texture size / cols` wide).
     pub padding: Option<(f32, f32)>,
+    /// Space around the sprite grid, at every texture edge. When set, the
+    /// texture follows the Tiled / LDtk layout: the padding is only left
+    /// between the sprites.
+    pub margin: Option<(f32, f32)>,
 }
 
 #[derive(Clone, Copy, Default)]
@@ -113,6 +166,46 @@ pub enum TextureFiltering {
     Linear,
 }
 
+/// Defines how the rendering resolution (see
+/// `GraphicsContext::set_rendering_resolution`) is mapped onto the window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum UpscaleMode {
+    /// Largest integer scale that fits the window, centered with bars
+    /// around (pixel perfect). The render is never cropped - in a window
+    /// smaller than the rendering resolution it is scaled down to fit.
+    #[default]
+    Integer,
+    /// Largest scale preserving the aspect ratio, centered with bars around.
+    Fit,
+    /// Fills the whole window, ignoring the aspect ratio.
+    Stretch,
+}
+impl UpscaleMode {
+    /// Returns the area covered by the upscaled image as `(origin, size)`,
+    /// in the window's physical pixels (bottom-left origin).
+    /// The area is in whole pixels and always within the window.
+    /// `vw`, `vh`: window size, `rw`, `rh`: rendering resolution.
+    pub fn get_rect(&self, vw: f32, vh: f32, rw: f32, rh: f32) -> (Vector2f, Vector2f) {
+        if rw <= 0. || rh <= 0. {
+            return (Vector2f::ZERO, Vector2f::new(vw, vh));
+        }
+        let fit = (vw / rw).min(vh / rh);
+        let scale = match self {
+            Self::Integer if fit >= 1. => fit.floor(),
+            Self::Integer | Self::Fit => fit,
+            Self::Stretch => return (Vector2f::ZERO, Vector2f::new(vw, vh)),
+        };
+        let w = (rw * scale).round().clamp(1., vw.max(1.));
+        let h = (rh * scale).round().clamp(1., vh.max(1.));
+        let origin = Vector2f::new(
+            (0.5 * (vw - w)).floor().max(0.),
+            (0.5 * (vh - h)).floor().max(0.),
+        );
+        (origin, Vector2f::new(w, h))
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum ShaderKind {
     Sprite,
@@ -123,8 +216,16 @@ pub enum ShaderKind {
 pub enum BuiltInShader {
     SpriteUnlit,
     SpriteLit,
+    /// Fog of war overlay (see `FogOfWar`)
+    Fog,
     Upscale,
     Lut,
+    /// Scene transition: cross fade
+    Fade,
+    /// Scene transition: left to right wipe
+    Wipe,
+    /// Blends a render layer over the layers below (used internally)
+    Composite,
 }
 
 #[derive(Clone, Copy)]
@@ -140,3 +241,61 @@ impl Default for AudioDeviceParams {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_inside(rect: (Vector2f, Vector2f), vw: f32, vh: f32) {
+        let (origin, size) = rect;
+        assert!(origin.x >= 0. && origin.y >= 0., "{:?}", rect);
+        assert!(
+            origin.x + size.x <= vw && origin.y + size.y <= vh,
+            "{:?}",
+            rect
+        );
+        assert_eq!(origin.x.fract() + size.x.fract(), 0.);
+        assert_eq!(origin.y.fract() + size.y.fract(), 0.);
+    }
+
+    #[test]
+    fn upscale_rect() {
+        let rect = UpscaleMode::Integer.get_rect(1000., 600., 320., 180.);
+        assert_eq!(rect, (Vector2f::new(20., 30.), Vector2f::new(960., 540.)));
+
+        let rect = UpscaleMode::Fit.get_rect(1000., 600., 320., 180.);
+        assert_eq!(rect, (Vector2f::new(0., 18.), Vector2f::new(1000., 563.)));
+
+        let rect = UpscaleMode::Stretch.get_rect(1000., 600., 320., 180.);
+        assert_eq!(rect, (Vector2f::ZERO, Vector2f::new(1000., 600.)));
+    }
+
+    #[test]
+    fn upscale_rect_small_window() {
+        // scaled down to fit, instead of cropped
+        let rect = UpscaleMode::Integer.get_rect(200., 100., 320., 180.);
+        assert_inside(rect, 200., 100.);
+        assert_eq!(rect.1.x, 178.);
+        assert_eq!(rect.1.y, 100.);
+
+        assert_inside(
+            UpscaleMode::Fit.get_rect(200., 100., 320., 180.),
+            200.,
+            100.,
+        );
+        assert_inside(UpscaleMode::Integer.get_rect(1., 1., 320., 180.), 1., 1.);
+    }
+
+    #[test]
+    fn upscale_rect_odd_sizes() {
+        for mode in [UpscaleMode::Integer, UpscaleMode::Fit] {
+            for (vw, vh) in [(1973., 1110.), (1111., 623.), (641., 361.), (333., 187.)] {
+                for (rw, rh) in [(480., 270.), (320., 180.), (321., 179.)] {
+                    assert_inside(mode.get_rect(vw, vh, rw, rh), vw, vh);
+                }
+            }
+        }
+        let rect = UpscaleMode::Fit.get_rect(1973., 1110., 480., 270.);
+        assert_eq!(rect, (Vector2f::ZERO, Vector2f::new(1973., 1110.)));
+    }
+}
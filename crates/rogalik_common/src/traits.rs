@@ -1,8 +1,11 @@
-use rogalik_math::vectors::Vector2f;
+use rogalik_math::vectors::{Vector2f, Vector2i};
 use std::sync::Arc;
 use winit::window::Window;
 
-use crate::structs::{BuiltInShader, Color, EngineError, ResourceId, ShaderKind, SpriteParams};
+use crate::camera::{CameraController, CameraFollowParams, CameraShakeParams};
+use crate::structs::{
+    BuiltInShader, Color, EngineError, ResourceId, ShaderKind, SpriteParams, UpscaleMode,
+};
 
 pub trait GraphicsSetup {
     /// Creates and initializes the graphics context and surface.
@@ -17,6 +20,11 @@ pub trait GraphicsSetup {
     /// seconds.
     /// (called by the engine internally)
     fn update_time(&mut self, delta: f32);
+    /// Advances the camera controllers (follow, shake, zoom) by the frame
+    /// `delta`. Runs after the scene update, so the cameras follow the
+    /// targets set in the same frame.
+    /// (called by the engine internally)
+    fn update_cameras(&mut self, _delta: f32) {}
     /// Updates and reloads any assets (e.g., textures, shaders) that have
     /// changed.
     /// (called by the engine internally)
@@ -31,6 +39,17 @@ pub trait GraphicsSetup {
     /// post-processing effects.
     /// (called by the engine internally)
     fn render(&mut self);
+    /// Starts drawing the outgoing frame of a scene transition.
+    /// Subsequent draw calls are rendered separately and blended with the
+    /// main frame by the `shader` (of the `PostProcess` kind), with
+    /// `progress` (0.0 to 1.0) passed as the pass strength.
+    /// The transition is applied to the current frame only.
+    /// (called by the engine internally)
+    fn begin_transition(&mut self, shader: ResourceId, progress: f32);
+    /// Ends drawing the outgoing frame of a scene transition.
+    /// Subsequent draw calls are queued for the main frame.
+    /// (called by the engine internally)
+    fn end_transition(&mut self);
     fn toggle_recording(&mut self);
 }
 
@@ -45,9 +64,30 @@ pub trait GraphicsContext {
     /// viewport size. `w`: The desired width for internal rendering.
     /// `h`: The desired height for internal rendering.
     fn set_rendering_resolution(&mut self, w: u32, h: u32);
+    /// Sets how the rendering resolution is mapped onto the window
+    /// (`UpscaleMode::Integer` by default). The bars are filled with the clear
+    /// color. Has no effect when the rendering resolution is not set.
+    fn set_upscale_mode(&mut self, mode: UpscaleMode);
     /// Loads a texture from the given file path and returns its `ResourceId`.
     /// `path`: The file path to the texture image.
     fn load_texture(&mut self, path: &str) -> ResourceId;
+    /// Creates a texture from raw pixels (eg. procedurally generated) and
+    /// returns its `ResourceId`.
+    /// `data`: tightly packed RGBA8 pixels, rows from top to bottom.
+    /// Returns an error if the data length does not match the dimensions
+    /// (or either of them is zero).
+    fn create_texture(&mut self, w: u32, h: u32, data: &[u8]) -> Result<ResourceId, EngineError>;
+    /// Updates a region of a texture created with `create_texture`
+    /// (can be called every frame).
+    /// `region`: (x, y, w, h) in pixels, with the top-left origin.
+    /// `data`: tightly packed RGBA8 pixels of the region.
+    /// An empty region is a no-op.
+    fn update_texture(
+        &mut self,
+        id: ResourceId,
+        region: (u32, u32, u32, u32),
+        data: &[u8],
+    ) -> Result<(), EngineError>;
     /// Loads a material with the given name and parameters.
     /// Materials define how objects are rendered, including their textures and
     /// shaders. `name`: A unique identifier for the material.
@@ -80,6 +120,28 @@ pub trait GraphicsContext {
     /// `params`: Parameters defining the post-process effect (e.g., shader,
     /// texture).
     fn add_post_process(&mut self, name: &str, params: crate::PostProcessParams);
+    /// Creates a named render layer, with its own render target and post
+    /// process chain. The layers are composited over the default layer, in
+    /// the creation order. Eg. a UI layer, drawn at the native resolution
+    /// and not affected by the world's post processing.
+    /// Creating an existing layer updates its params.
+    fn create_layer(&mut self, name: &str, params: crate::LayerParams);
+    /// Creates an offscreen render target of the given size (in pixels) and
+    /// returns its texture id, which can be used as a material's
+    /// `diffuse_texture` (eg. for minimaps or cached tilemap chunks).
+    /// The target has its own camera, initially showing the
+    /// (0, 0) - (w, h) area. It is selected by `set_render_target`.
+    fn create_render_target(&mut self, w: u32, h: u32) -> ResourceId;
+    /// Directs the subsequent draw calls into the render target (and selects
+    /// its camera). `None` switches back to the screen (and the previous
+    /// camera). The target is re-rendered only in the frames it is drawn
+    /// into, otherwise it keeps the content. A target should not be drawn
+    /// with a material using its own texture.
+    fn set_render_target(&mut self, target: Option<ResourceId>) -> Result<(), EngineError>;
+    /// Directs the subsequent draw calls to the layer (and selects its
+    /// camera, if set). Use `DEFAULT_LAYER` to switch back (the camera
+    /// replaced by the layer's camera is then selected again).
+    fn set_layer(&mut self, name: &str) -> Result<(), EngineError>;
     /// Queues a standard sprite for drawing in the next render pass.
     /// This method uses the material's default (1x1) atlas or the first sprite
     /// in a defined atlas. `material`: The name of the material to use for
@@ -145,6 +207,31 @@ pub trait GraphicsContext {
         indices: &[u16],
         z_index: i32,
     ) -> Result<(), EngineError>;
+    /// Creates a tilemap drawn with the `material`'s atlas sprites.
+    /// The tiles are kept in chunks with cached GPU buffers (rebuilt only
+    /// when the chunk changes), which makes it much cheaper to draw than
+    /// the individual sprites. Returns the tilemap's id.
+    fn create_tilemap(
+        &mut self,
+        material: &str,
+        params: crate::TilemapParams,
+    ) -> Result<ResourceId, EngineError>;
+    /// Sets the tile (atlas index) of the tilemap's layer at the grid
+    /// `position`. `None` clears the tile.
+    fn set_tile(
+        &mut self,
+        tilemap: ResourceId,
+        layer: usize,
+        position: Vector2i,
+        tile: Option<usize>,
+    ) -> Result<(), EngineError>;
+    /// Returns the tile (atlas index) of the tilemap's layer at the grid
+    /// `position`.
+    fn get_tile(&self, tilemap: ResourceId, layer: usize, position: Vector2i) -> Option<usize>;
+    /// Queues the tilemap for drawing with the current camera.
+    /// Only the chunks within the camera's view are drawn. Each layer is
+    /// drawn at its z-index, below the sprites of the same z-index.
+    fn draw_tilemap(&mut self, tilemap: ResourceId) -> Result<(), EngineError>;
     /// Adds a point light source to the scene for the current frame.
     /// Lights are reset at the end of each frame.
     /// `position`: The world position of the light source.
@@ -251,9 +338,114 @@ pub trait Camera {
     /// Converts a point from camera coordinates to world coordinates.
     /// `v`: The vector in camera coordinates.
     fn camera_to_world(&self, v: Vector2f) -> Vector2f;
+    /// Converts a point from world coordinates to camera coordinates
+    /// (physical pixels, bottom-left origin). Inverse of `camera_to_world`.
+    /// Useful to anchor UI elements to world objects.
+    /// `v`: The vector in world coordinates.
+    fn world_to_camera(&self, v: Vector2f) -> Vector2f {
+        // the default inverts `camera_to_world`, assuming an affine transform
+        let origin = self.camera_to_world(Vector2f::ZERO);
+        let ex = self.camera_to_world(Vector2f::new(1., 0.)) - origin;
+        let ey = self.camera_to_world(Vector2f::new(0., 1.)) - origin;
+        let det = ex.x * ey.y - ey.x * ex.y;
+        if det == 0. {
+            return Vector2f::ZERO;
+        }
+        let d = v - origin;
+        Vector2f::new(
+            (d.x * ey.y - ey.x * d.y) / det,
+            (ex.x * d.y - d.x * ex.y) / det,
+        )
+    }
+    /// Returns the camera's rotation in radians (counter-clockwise).
+    fn get_rotation(&self) -> f32 {
+        0.
+    }
+    /// Sets the camera's rotation in radians (counter-clockwise).
+    /// Ignored by the cameras that do not support the rotation.
+    fn set_rotation(&mut self, _rotation: f32) {}
+    /// Returns the camera's viewport (see `set_viewport`).
+    fn get_viewport(&self) -> Option<(Vector2f, Vector2f)> {
+        None
+    }
+    /// Limits the camera's drawing to a part of the render target,
+    /// eg. for split-screen or a minimap.
+    /// The viewport is a tuple `(origin, size)`, in the 0.0 to 1.0 range of
+    /// the target size, with the bottom-left origin. Parts outside of the
+    /// target are cut off.
+    /// `None` resets the viewport to the full target.
+    /// Ignored by the cameras that do not support the viewports.
+    fn set_viewport(&mut self, _viewport: Option<(Vector2f, Vector2f)>) {}
+    /// Returns whether the camera snaps to the rendering pixels.
+    fn get_pixel_snap(&self) -> bool {
+        false
+    }
+    /// When enabled, the rendered view is aligned to whole rendering pixels,
+    /// which prevents the shimmering of pixel art when the camera moves by
+    /// sub-pixel amounts (eg. while following). The target itself stays
+    /// unchanged. Intended for the non-rotated cameras.
+    /// Ignored by the cameras that do not support the snapping.
+    fn set_pixel_snap(&mut self, _value: bool) {}
     /// Returns the current rectangular bounds of the camera's view in world
     /// coordinates. The return value is a tuple `(min_vector, max_vector)`
     /// representing the bottom-left and top-right corners of the camera's
-    /// view.
+    /// view (its bounding box, when rotated).
     fn get_bounds(&self) -> (Vector2f, Vector2f);
+    /// Returns the controller handling the follow, bounds, shake and zoom.
+    /// Cameras without a controller ignore the methods below.
+    fn get_controller(&self) -> Option<&CameraController> {
+        None
+    }
+    /// Returns the mutable controller handling the follow, bounds, shake and
+    /// zoom.
+    fn get_controller_mut(&mut self) -> Option<&mut CameraController> {
+        None
+    }
+    /// Advances the camera controller by the frame `delta` (in seconds).
+    /// (called by the engine internally)
+    fn update(&mut self, _delta: f32) {}
+    /// Makes the camera smoothly follow the point (in world coordinates).
+    /// Should be called every frame with the followed object's position.
+    fn follow(&mut self, target: Vector2f) {
+        if let Some(controller) = self.get_controller_mut() {
+            controller.follow(target);
+        }
+    }
+    /// Stops following. The camera stays at its current target.
+    fn stop_follow(&mut self) {
+        if let Some(controller) = self.get_controller_mut() {
+            controller.stop_follow();
+        }
+    }
+    fn set_follow_params(&mut self, params: CameraFollowParams) {
+        if let Some(controller) = self.get_controller_mut() {
+            controller.follow_params = params;
+        }
+    }
+    /// Limits the camera's view to the world area, given as a tuple
+    /// `(min_vector, max_vector)`. `None` removes the limit.
+    fn set_world_bounds(&mut self, bounds: Option<(Vector2f, Vector2f)>) {
+        if let Some(controller) = self.get_controller_mut() {
+            controller.set_world_bounds(bounds);
+        }
+    }
+    /// Adds the shake trauma (the total is kept in the 0.0 to 1.0 range).
+    /// The trauma decays over time (see `CameraShakeParams`).
+    fn shake(&mut self, trauma: f32) {
+        if let Some(controller) = self.get_controller_mut() {
+            controller.shake(trauma);
+        }
+    }
+    fn set_shake_params(&mut self, params: CameraShakeParams) {
+        if let Some(controller) = self.get_controller_mut() {
+            controller.shake_params = params;
+        }
+    }
+    /// Smoothly changes the camera's scale over the `duration` (in seconds).
+    fn zoom_to(&mut self, scale: f32, duration: f32) {
+        let current = self.get_scale();
+        if let Some(controller) = self.get_controller_mut() {
+            controller.zoom_to(current, scale, duration);
+        }
+    }
 }
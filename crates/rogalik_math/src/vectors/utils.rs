@@ -137,6 +137,7 @@ fn transform_octant(v: Vector2i, octant: u32) -> Vector2i {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn visible_octant(
     origin: Vector2i,
     tiles: &HashSet<Vector2i>,
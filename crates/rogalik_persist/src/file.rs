@@ -38,7 +38,7 @@ impl KVStore for FileStore {
     }
     fn store_raw(key: &str, value: &[u8], path: Option<&str>) -> Result<()> {
         let mut f = File::create(FileStore::get_path(key, path)?)?;
-        f.write(value)?;
+        f.write_all(value)?;
         Ok(())
     }
     fn remove(key: &str, path: Option<&str>) -> Result<()> {
@@ -6,6 +6,8 @@ use rogalik_common::SpriteParams;
 #[derive(Clone, Copy, Debug, Default)]
 pub struct SpriteAtlas {
     cols: usize,
+    u_offset: f32,
+    v_offset: f32,
     pub u_step: f32,
     pub v_step: f32,
     u_size: f32,
@@ -19,12 +21,27 @@ impl SpriteAtlas {
         rows: usize,
         cols: usize,
         padding: Option<(f32, f32)>,
+        margin: Option<(f32, f32)>,
     ) -> Self {
-        let (sp_w, sp_h) = sprite_pixel_size(texture_size.0, texture_size.1, rows, cols, padding);
+        let (sp_w, sp_h) =
+            sprite_pixel_size(texture_size.0, texture_size.1, rows, cols, padding, margin);
+        let (u_step, v_step) = match margin {
+            None => (1.0 / cols as f32, 1.0 / rows as f32),
+            Some(_) => {
+                let (pad_x, pad_y) = padding.unwrap_or_default();
+                (
+                    (sp_w + pad_x) / texture_size.0 as f32,
+                    (sp_h + pad_y) / texture_size.1 as f32,
+                )
+            }
+        };
+        let (margin_x, margin_y) = margin.unwrap_or_default();
         Self {
             cols,
-            u_step: 1.0 / cols as f32,
-            v_step: 1.0 / rows as f32,
+            u_offset: margin_x / texture_size.0 as f32,
+            v_offset: margin_y / texture_size.1 as f32,
+            u_step,
+            v_step,
             u_size: sp_w / texture_size.0 as f32,
             v_size: sp_h / texture_size.1 as f32,
             sprite_w: sp_w,
@@ -43,8 +60,8 @@ impl SpriteAtlas {
     ) -> ([Vertex; 4], [u16; 6]) {
         let row = index / self.cols;
         let col = index % self.cols;
-        let u = self.u_step * col as f32;
-        let v = self.v_step * row as f32;
+        let u = self.u_offset + self.u_step * col as f32;
+        let v = self.v_offset + self.v_step * row as f32;
 
         let color = params.color.as_srgb();
         let l = u;
@@ -115,8 +132,8 @@ impl SpriteAtlas {
     ) -> ([Vertex; 16], [u16; 54]) {
         let row = index / self.cols;
         let col = index % self.cols;
-        let u = self.u_step * col as f32;
-        let v = self.v_step * row as f32;
+        let u = self.u_offset + self.u_step * col as f32;
+        let v = self.v_offset + self.v_step * row as f32;
 
         let color = params.color.as_srgb();
 
@@ -184,14 +201,19 @@ pub fn sprite_pixel_size(
     rows: usize,
     cols: usize,
     padding: Option<(f32, f32)>,
+    margin: Option<(f32, f32)>,
 ) -> (f32, f32) {
-    let grid_width = (texture_w as f32) / (cols as f32);
-    let grid_height = (texture_h as f32) / (rows as f32);
-
-    match padding {
-        None => (grid_width, grid_height),
-        Some((x, y)) => (grid_width - x, grid_height - y),
-    }
+    let (pad_x, pad_y) = padding.unwrap_or_default();
+    let Some((margin_x, margin_y)) = margin else {
+        let grid_width = (texture_w as f32) / (cols as f32);
+        let grid_height = (texture_h as f32) / (rows as f32);
+        return (grid_width - pad_x, grid_height - pad_y);
+    };
+    // the padding is only between the sprites
+    (
+        (texture_w as f32 - 2. * margin_x - (cols - 1) as f32 * pad_x) / cols as f32,
+        (texture_h as f32 - 2. * margin_y - (rows - 1) as f32 * pad_y) / rows as f32,
+    )
 }
 
 fn rotate_verts(vertices: &mut [Vertex], angle: f32, cx: f32, cy: f32) {
@@ -212,3 +234,51 @@ fn rotate_verts(vertices: &mut [Vertex], angle: f32, cx: f32, cy: f32) {
         v.position[1] += cy;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_uv_rect(atlas: &SpriteAtlas, index: usize) -> [f32; 4] {
+        let (vertices, _) = atlas.get_sprite(
+            index,
+            Vector2f::ZERO,
+            Vector2f::new(1., 1.),
+            SpriteParams::default(),
+        );
+        [
+            vertices[0].tex_coords[0],
+            vertices[2].tex_coords[1],
+            vertices[2].tex_coords[0],
+            vertices[0].tex_coords[1],
+        ]
+    }
+
+    fn assert_uv_rect(atlas: &SpriteAtlas, index: usize, expected: [f32; 4]) {
+        let rect = get_uv_rect(atlas, index);
+        assert!(
+            rect.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-6),
+            "{rect:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn padding_after_sprites() {
+        let atlas = SpriteAtlas::new((18, 9), 1, 2, Some((1., 1.)), None);
+        assert_eq!(atlas.get_sprite_size(), (8., 8.));
+        assert_uv_rect(&atlas, 1, [9. / 18., 0., 17. / 18., 8. / 9.]);
+    }
+
+    #[test]
+    fn padding_between_sprites() {
+        // Tiled layout: 2 columns of 8px tiles with the spacing 1
+        let atlas = SpriteAtlas::new((17, 8), 1, 2, Some((1., 1.)), Some((0., 0.)));
+        assert_eq!(atlas.get_sprite_size(), (8., 8.));
+        assert_uv_rect(&atlas, 0, [0., 0., 8. / 17., 1.]);
+        assert_uv_rect(&atlas, 1, [9. / 17., 0., 1., 1.]);
+
+        let atlas = SpriteAtlas::new((21, 12), 1, 2, Some((1., 1.)), Some((2., 2.)));
+        assert_eq!(atlas.get_sprite_size(), (8., 8.));
+        assert_uv_rect(&atlas, 1, [11. / 21., 2. / 12., 19. / 21., 10. / 12.]);
+    }
+}
@@ -1,6 +1,6 @@
 use wgpu::util::DeviceExt;
 
-use rogalik_common::{Camera, EngineError};
+use rogalik_common::{Camera, CameraController, EngineError, UpscaleMode};
 use rogalik_math::vectors::Vector2f;
 
 const Z_RANGE: f32 = 100.;
@@ -8,10 +8,18 @@ const Z_RANGE: f32 = 100.;
 pub struct Camera2D {
     scale: f32,
     target: Vector2f,
+    rotation: f32,
+    // normalized (origin, size)
+    viewport: Option<(Vector2f, Vector2f)>,
     vw: f32, // viewport
     vh: f32,
     rw: f32, // rendering
     rh: f32,
+    upscale_mode: UpscaleMode,
+    pixel_snap: bool,
+    // not resized with the window (eg. render target cameras)
+    fixed_size: bool,
+    controller: CameraController,
     bind_group: Option<wgpu::BindGroup>,
     buffer: Option<wgpu::Buffer>,
 }
@@ -23,38 +31,96 @@ impl Camera for Camera2D {
         self.target
     }
     fn set_scale(&mut self, scale: f32) {
+        self.controller.stop_zoom();
         self.scale = scale;
     }
     fn set_target(&mut self, target: Vector2f) {
-        self.target = target;
+        self.target = self
+            .controller
+            .clamp_target(target, 0.5 * self.get_view_size() / self.scale);
     }
     fn camera_to_world(&self, v: Vector2f) -> Vector2f {
-        // in physical pixels
-        let x = v.x * self.rw / self.vw;
-        let y = v.y * self.rh / self.vh;
+        // in physical pixels, the letterbox bars are outside of the render
+        let (screen_origin, screen_size) = self.get_screen_rect();
+        let x = (v.x - screen_origin.x) * self.rw / screen_size.x;
+        let y = (v.y - screen_origin.y) * self.rh / screen_size.y;
+        let (origin, size) = self.get_viewport_px();
+        let local = (Vector2f::new(x, y) - origin - 0.5 * size) / self.scale;
+        rotate(local, self.rotation) + self.snap(self.target)
+    }
+    fn world_to_camera(&self, v: Vector2f) -> Vector2f {
+        let (screen_origin, screen_size) = self.get_screen_rect();
+        let (origin, size) = self.get_viewport_px();
+        let local =
+            self.scale * rotate(v - self.snap(self.target), -self.rotation) + origin + 0.5 * size;
         Vector2f::new(
-            (x - 0.5 * self.rw) / self.scale + self.target.x,
-            (y - 0.5 * self.rh) / self.scale + self.target.y,
+            local.x * screen_size.x / self.rw + screen_origin.x,
+            local.y * screen_size.y / self.rh + screen_origin.y,
         )
     }
     fn get_bounds(&self) -> (Vector2f, Vector2f) {
-        let hx = 0.5 * self.rw / self.scale;
-        let hy = 0.5 * self.rh / self.scale;
+        let half = 0.5 * self.get_view_size() / self.scale;
+        let (sin, cos) = self.rotation.sin_cos();
+        // bounding box of the rotated view
+        let hx = cos.abs() * half.x + sin.abs() * half.y;
+        let hy = sin.abs() * half.x + cos.abs() * half.y;
         (
             Vector2f::new(self.target.x - hx, self.target.y - hy),
             Vector2f::new(self.target.x + hx, self.target.y + hy),
         )
     }
+    fn get_rotation(&self) -> f32 {
+        self.rotation
+    }
+    fn set_rotation(&mut self, rotation: f32) {
+        self.rotation = rotation;
+    }
+    fn get_viewport(&self) -> Option<(Vector2f, Vector2f)> {
+        self.viewport
+    }
+    fn set_viewport(&mut self, viewport: Option<(Vector2f, Vector2f)>) {
+        // cut off to the target, as the gpu viewport can't exceed it
+        self.viewport = viewport.map(|(origin, size)| {
+            let x0 = origin.x.clamp(0., 1.);
+            let y0 = origin.y.clamp(0., 1.);
+            let x1 = (origin.x + size.x).clamp(x0, 1.);
+            let y1 = (origin.y + size.y).clamp(y0, 1.);
+            (Vector2f::new(x0, y0), Vector2f::new(x1 - x0, y1 - y0))
+        });
+    }
+    fn get_pixel_snap(&self) -> bool {
+        self.pixel_snap
+    }
+    fn set_pixel_snap(&mut self, value: bool) {
+        self.pixel_snap = value;
+    }
+    fn get_controller(&self) -> Option<&CameraController> {
+        Some(&self.controller)
+    }
+    fn get_controller_mut(&mut self) -> Option<&mut CameraController> {
+        Some(&mut self.controller)
+    }
+    fn update(&mut self, delta: f32) {
+        let view_size = self.get_view_size();
+        self.controller
+            .update(delta, &mut self.target, &mut self.scale, view_size);
+    }
 }
 impl Camera2D {
     pub fn new(vw: f32, vh: f32, rw: f32, rh: f32, scale: f32, target: Vector2f) -> Self {
         Self {
             scale,
             target,
+            rotation: 0.,
+            viewport: None,
             vw,
             vh,
             rw,
             rh,
+            upscale_mode: UpscaleMode::default(),
+            pixel_snap: false,
+            fixed_size: false,
+            controller: CameraController::default(),
             bind_group: None,
             buffer: None,
         }
@@ -81,20 +147,93 @@ impl Camera2D {
         self.rw = rw;
         self.rh = rh;
     }
+    pub fn set_fixed_size(&mut self, value: bool) {
+        self.fixed_size = value;
+    }
+    pub fn is_fixed_size(&self) -> bool {
+        self.fixed_size
+    }
+    pub fn set_upscale_mode(&mut self, mode: UpscaleMode) {
+        self.upscale_mode = mode;
+    }
+    pub fn get_render_size(&self) -> (f32, f32) {
+        (self.rw, self.rh)
+    }
+    /// Returns the viewport rect in the render target pixels:
+    /// (x, y, w, h) with the top-left origin, as expected by wgpu.
+    pub fn get_viewport_rect(&self) -> (f32, f32, f32, f32) {
+        let (origin, size) = self.get_viewport_px();
+        (origin.x, self.rh - origin.y - size.y, size.x, size.y)
+    }
+    pub fn get_window_size(&self) -> (f32, f32) {
+        (self.vw, self.vh)
+    }
+    /// Returns the viewport rect in the window pixels (x, y, w, h with the
+    /// top-left origin), for the targets rendered at the native resolution.
+    /// Covers the same area of the window as the upscaled viewport.
+    pub fn get_window_viewport_rect(&self) -> (f32, f32, f32, f32) {
+        let (screen_origin, screen_size) = self.get_screen_rect();
+        let (origin, size) = self.get_viewport_px();
+        let sx = screen_size.x / self.rw;
+        let sy = screen_size.y / self.rh;
+        let (w, h) = (size.x * sx, size.y * sy);
+        let x = screen_origin.x + origin.x * sx;
+        let y = screen_origin.y + origin.y * sy;
+        (x, self.vh - y - h, w, h)
+    }
+    // viewport (origin, size) in rendering pixels, bottom-left origin
+    fn get_viewport_px(&self) -> (Vector2f, Vector2f) {
+        let (origin, size) = self
+            .viewport
+            .unwrap_or((Vector2f::ZERO, Vector2f::new(1., 1.)));
+        (
+            Vector2f::new(origin.x * self.rw, origin.y * self.rh),
+            Vector2f::new(size.x * self.rw, size.y * self.rh),
+        )
+    }
+    // area of the window covered by the upscaled render, in physical pixels
+    fn get_screen_rect(&self) -> (Vector2f, Vector2f) {
+        self.upscale_mode
+            .get_rect(self.vw, self.vh, self.rw, self.rh)
+    }
+    // aligns the view to the rendering pixels, if the snapping is enabled
+    fn snap(&self, target: Vector2f) -> Vector2f {
+        if !self.pixel_snap {
+            return target;
+        }
+        // viewport center in the rendering pixels
+        let (origin, size) = self.get_viewport_px();
+        let center = origin + 0.5 * size;
+        Vector2f::new(
+            ((target.x * self.scale - center.x).round() + center.x) / self.scale,
+            ((target.y * self.scale - center.y).round() + center.y) / self.scale,
+        )
+    }
+    // viewport size at the scale of 1.
+    fn get_view_size(&self) -> Vector2f {
+        self.get_viewport_px().1
+    }
     fn get_matrix(&self) -> [[f32; 4]; 4] {
-        let zoom = 1. / self.scale;
         let n = -Z_RANGE;
         let f = Z_RANGE;
-        let l = self.target.x - zoom * self.rw / 2.;
-        let r = self.target.x + zoom * self.rw / 2.;
-        let t = self.target.y - zoom * self.rh / 2.;
-        let b = self.target.y + zoom * self.rh / 2.;
+        let size = self.get_view_size() / self.scale;
+        let sx = 2. / size.x;
+        let sy = 2. / size.y;
+        let (sin, cos) = self.rotation.sin_cos();
+        // the shake is applied to the rendered view only
+        let target = self.snap(self.target + self.controller.get_shake_offset());
 
+        // orthographic projection * rotation(-angle) * translation(-target)
         [
-            [2. / (r - l), 0., 0., 0.],
-            [0., 2. / (b - t), 0., 0.],
+            [sx * cos, -sy * sin, 0., 0.],
+            [sx * sin, sy * cos, 0., 0.],
             [0., 0., 1. / (f - n), 0.],
-            [-(r + l) / (r - l), -(b + t) / (b - t), -n / (f - n), 1.],
+            [
+                -sx * (cos * target.x + sin * target.y),
+                -sy * (cos * target.y - sin * target.x),
+                -n / (f - n),
+                1.,
+            ],
         ]
     }
     fn create_bind_group(
@@ -124,3 +263,83 @@ pub fn get_camera_buffer(device: &wgpu::Device, matrix: [[f32; 4]; 4]) -> wgpu::
         usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
     })
 }
+
+fn rotate(v: Vector2f, angle: f32) -> Vector2f {
+    let (sin, cos) = angle.sin_cos();
+    Vector2f::new(cos * v.x - sin * v.y, sin * v.x + cos * v.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn world_to_camera_inverse() {
+        let mut camera = Camera2D::new(200., 100., 100., 50., 2., Vector2f::new(10., -5.));
+        camera.set_rotation(0.5);
+        camera.set_viewport(Some((Vector2f::new(0.5, 0.), Vector2f::new(0.5, 1.))));
+
+        // viewport center maps to the target
+        let center = camera.world_to_camera(camera.get_target());
+        assert!((center - Vector2f::new(150., 50.)).len() < 1e-4);
+
+        for v in [
+            Vector2f::ZERO,
+            Vector2f::new(130., 20.),
+            Vector2f::new(199., 99.),
+        ] {
+            let w = camera.camera_to_world(v);
+            assert!((camera.world_to_camera(w) - v).len() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn viewport_clamped() {
+        let mut camera = Camera2D::new(64., 64., 64., 64., 1., Vector2f::ZERO);
+        camera.set_viewport(Some((Vector2f::new(0.75, -0.5), Vector2f::new(0.5, 1.))));
+        assert_eq!(
+            camera.get_viewport(),
+            Some((Vector2f::new(0.75, 0.), Vector2f::new(0.25, 0.5)))
+        );
+        assert_eq!(camera.get_viewport_rect(), (48., 32., 16., 32.));
+    }
+
+    #[test]
+    fn rotated_bounds() {
+        let mut camera = Camera2D::new(100., 50., 100., 50., 1., Vector2f::ZERO);
+        camera.set_rotation(std::f32::consts::FRAC_PI_2);
+        let (min, max) = camera.get_bounds();
+        assert!((min - Vector2f::new(-25., -50.)).len() < 1e-4);
+        assert!((max - Vector2f::new(25., 50.)).len() < 1e-4);
+    }
+
+    #[test]
+    fn letterbox_mapping() {
+        // 64x32 render in a 200x100 window -> 3x scale, 4px bars on the sides
+        let mut camera = Camera2D::new(200., 100., 64., 32., 1., Vector2f::ZERO);
+        assert_eq!(
+            camera.camera_to_world(Vector2f::new(4., 2.)),
+            Vector2f::new(-32., -16.)
+        );
+        assert_eq!(
+            camera.world_to_camera(Vector2f::new(32., 16.)),
+            Vector2f::new(196., 98.)
+        );
+
+        camera.set_upscale_mode(UpscaleMode::Stretch);
+        assert_eq!(
+            camera.camera_to_world(Vector2f::new(200., 100.)),
+            Vector2f::new(32., 16.)
+        );
+    }
+
+    #[test]
+    fn pixel_snap() {
+        let mut camera = Camera2D::new(30., 30., 15., 15., 2., Vector2f::new(1.1, 0.));
+        camera.set_pixel_snap(true);
+        // the world origin lands on a whole rendering pixel
+        let origin = camera.world_to_camera(Vector2f::ZERO) / 2.;
+        assert_eq!(origin, origin.round());
+        assert_eq!(camera.get_target(), Vector2f::new(1.1, 0.));
+    }
+}
@@ -0,0 +1,45 @@
+// Vertex shader
+struct CameraUniform {
+    view_proj: mat4x4<f32>
+};
+@group(1) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) tex_coords: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) tex_coords: vec2<f32>
+};
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.color = model.color;
+    out.tex_coords = model.tex_coords;
+    out.clip_position = camera.view_proj * vec4<f32>(model.position, 1.0);
+    return out;
+}
+
+
+// Fragment shader
+@group(0) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(0) @binding(1)
+var s_diffuse: sampler;
+
+// The texture holds the fog opacity per tile (in the alpha channel).
+// Stretched with the linear filtering it fades between the tile centers,
+// the smoothstep sharpens the fade a little.
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let opacity = smoothstep(0.0, 1.0, textureSample(t_diffuse, s_diffuse, in.tex_coords).a);
+    return vec4<f32>(in.color.rgb, opacity * in.color.a);
+}
@@ -26,17 +26,7 @@ fn vs_main(
     );
     out.clip_position = vec4<f32>(out.uv * 2.0 - 1.0, 0.0, 1.0);
     out.uv.y = 1.0 - out.uv.y;
-
-    var scale_u = round(globals.viewport_size.x / globals.render_size.x);
-    var scaled_rw = scale_u * globals.render_size.x;
-    var ru = (scaled_rw - globals.viewport_size.x) / scaled_rw;
-    out.uv.x -= out.uv.x * ru;
-
-    var scale_v = round(globals.viewport_size.y / globals.render_size.y);
-    var scaled_rh = scale_v * globals.render_size.y;
-    var rv = (scaled_rh - globals.viewport_size.y) / scaled_rh;
-    out.uv.y -= out.uv.y * rv;
-    
+    // the scaling and letterboxing are done by the pass viewport
     return out;
 }
 
@@ -0,0 +1,51 @@
+struct VertexOutput {
+    @location(0) uv: vec2<f32>,
+    @builtin(position) clip_position: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vi: u32
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.uv = vec2<f32>(
+        f32((vi << 1u) & 2u),
+        f32(vi & 2u),
+    );
+    out.clip_position = vec4<f32>(out.uv * 2.0 - 1.0, 0.0, 1.0);
+    out.uv.y = 1.0 - out.uv.y;
+    return out;
+}
+
+struct PostProcessUniform {
+    strength: f32,
+}
+
+// incoming scene
+@group(0)
+@binding(0)
+var input_image: texture_2d<f32>;
+
+@group(0)
+@binding(1)
+var input_sampler: sampler;
+
+// outgoing scene
+@group(0)
+@binding(2)
+var prev_image: texture_2d<f32>;
+
+@group(0)
+@binding(3)
+var prev_sampler: sampler;
+
+@group(0)
+@binding(4)
+var<uniform> params: PostProcessUniform;
+
+@fragment
+fn fs_main(vs: VertexOutput) -> @location(0) vec4<f32> {
+    let col = textureSample(input_image, input_sampler, vs.uv);
+    let prev = textureSample(prev_image, prev_sampler, vs.uv);
+    return mix(prev, col, params.strength);
+}
@@ -36,7 +36,7 @@ impl Material {
     }
     pub fn create_wgpu_data(
         &mut self,
-        textures: &Vec<TextureData>,
+        textures: &[TextureData],
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         bind_group_layout: &wgpu::BindGroupLayout,
@@ -49,8 +49,8 @@ impl Material {
             .ok_or(EngineError::ResourceNotFound)?;
 
         self.bind_group = Some(get_material_bind_group(
-            &diffuse_texture,
-            &normal_texture,
+            diffuse_texture,
+            normal_texture,
             device,
             queue,
             bind_group_layout,
@@ -64,10 +64,11 @@ impl Material {
                 atlas_params.rows,
                 atlas_params.cols,
                 atlas_params.padding,
+                atlas_params.margin,
             ))
         } else {
             // Create 1x1 atlas for compatibility.
-            self.atlas = Some(SpriteAtlas::new(diffuse_texture.dim, 1, 1, None));
+            self.atlas = Some(SpriteAtlas::new(diffuse_texture.dim, 1, 1, None, None));
         }
 
         Ok(())
@@ -83,8 +84,7 @@ fn get_material_bind_group(
     address_mode: wgpu::AddressMode,
     filter_mode: wgpu::FilterMode,
 ) -> wgpu::BindGroup {
-    let diffuse_texture = diffuse_data.to_wgpu_texture(device, queue, false);
-    let diff_tex_view = diffuse_texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let diff_tex_view = diffuse_data.get_view(device, queue, false);
     let diff_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
         address_mode_u: address_mode,
         address_mode_v: address_mode,
@@ -95,8 +95,7 @@ fn get_material_bind_group(
         ..Default::default()
     });
 
-    let normal_texture = normal_data.to_wgpu_texture(device, queue, true);
-    let normal_tex_view = normal_texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let normal_tex_view = normal_data.get_view(device, queue, true);
     let normal_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
         address_mode_u: address_mode,
         address_mode_v: address_mode,
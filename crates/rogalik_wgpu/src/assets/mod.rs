@@ -6,7 +6,7 @@ use std::{
 use rogalik_assets::{AssetContext, AssetState, AssetStore};
 use rogalik_common::{
     AtlasParams, BuiltInShader, EngineError, MaterialParams, PostProcessParams, ResourceId,
-    ShaderKind,
+    ShaderKind, TilemapParams,
 };
 use rogalik_math::vectors::Vector2f;
 
@@ -18,6 +18,7 @@ pub mod material;
 pub mod postprocess;
 pub mod shader;
 mod texture;
+pub mod tilemap;
 
 pub struct WgpuAssets {
     asset_store: Arc<Mutex<AssetStore>>,
@@ -34,6 +35,7 @@ pub struct WgpuAssets {
     postprocess_names: HashMap<String, ResourceId>, // lookup
     shaders: Vec<shader::Shader>,
     pub(crate) textures: Vec<texture::TextureData>,
+    pub(crate) tilemaps: Vec<tilemap::Tilemap>,
 }
 impl WgpuAssets {
     pub fn new(asset_store: Arc<Mutex<AssetStore>>) -> Self {
@@ -53,6 +55,7 @@ impl WgpuAssets {
             postprocess_names: HashMap::new(),
             shaders: Vec::new(),
             textures: Vec::new(),
+            tilemaps: Vec::new(),
         };
         assets.load_builtins();
         assets
@@ -69,6 +72,11 @@ impl WgpuAssets {
             ShaderKind::Sprite,
             BuiltInShader::SpriteLit,
         );
+        self.load_builtin_shader(
+            include_bytes!("include/fog.wgsl"),
+            ShaderKind::Sprite,
+            BuiltInShader::Fog,
+        );
         self.load_builtin_shader(
             include_bytes!("include/sprite_pass_upscale.wgsl"),
             ShaderKind::PostProcess,
@@ -79,6 +87,29 @@ impl WgpuAssets {
             ShaderKind::PostProcess,
             BuiltInShader::Lut,
         );
+        self.load_builtin_shader(
+            include_bytes!("include/transition_fade.wgsl"),
+            ShaderKind::PostProcess,
+            BuiltInShader::Fade,
+        );
+        self.load_builtin_shader(
+            include_bytes!("include/transition_wipe.wgsl"),
+            ShaderKind::PostProcess,
+            BuiltInShader::Wipe,
+        );
+        self.load_builtin_shader(
+            include_bytes!("include/layer_composite.wgsl"),
+            ShaderKind::PostProcess,
+            BuiltInShader::Composite,
+        );
+        // layers are blended over the ones below
+        if let Some(shader) = self
+            .builtin_shaders
+            .get(&BuiltInShader::Composite)
+            .and_then(|id| self.shaders.get_mut(id.0))
+        {
+            shader.overlay = true;
+        }
 
         self.default_normal = self.texture_from_bytes(include_bytes!("include/default_normal.png"));
         self.default_diffuse = self.texture_from_bytes(include_bytes!("include/white.png"));
@@ -93,6 +124,9 @@ impl WgpuAssets {
     ) -> Result<(), EngineError> {
         self.create_bind_group_layouts(device);
         self.create_pipeline_layouts(device)?;
+        for texture in self.textures.iter_mut() {
+            texture.create_gpu_texture(device, queue, texture_format);
+        }
         let mut store = self
             .asset_store
             .lock()
@@ -142,7 +176,7 @@ impl WgpuAssets {
         for pass in self.postprocess.iter_mut() {
             pass.create_wgpu_data(
                 &self.textures,
-                &postprocess_layout,
+                postprocess_layout,
                 w,
                 h,
                 device,
@@ -171,14 +205,17 @@ impl WgpuAssets {
         let mut updated_textures = HashSet::new();
 
         for (i, texture) in self.textures.iter_mut().enumerate() {
-            if let Some(asset) = store.get(texture.asset_id) {
+            let Some(asset_id) = texture.asset_id else {
+                continue;
+            };
+            if let Some(asset) = store.get(asset_id) {
                 if asset.state == AssetState::Updated {
-                    log::debug!("Updating texture {}, Asset: {:?}", i, texture.asset_id);
+                    log::debug!("Updating texture {}, Asset: {:?}", i, asset_id);
                     texture.update_bytes(asset.data.get());
                     updated_textures.insert(i);
 
                     #[cfg(debug_assertions)]
-                    store.mark_read(texture.asset_id);
+                    store.mark_read(asset_id);
                 }
             }
         }
@@ -188,8 +225,9 @@ impl WgpuAssets {
                 || updated_textures.contains(&material.normal_texture_id.0)
             {
                 log::debug!("Updating material {:?}", material);
-                if let Err(_) =
-                    material.create_wgpu_data(&self.textures, device, queue, material_layout)
+                if material
+                    .create_wgpu_data(&self.textures, device, queue, material_layout)
+                    .is_err()
                 {
                     log::error!("Material reload failed!");
                 }
@@ -197,8 +235,9 @@ impl WgpuAssets {
         }
 
         for shader in self.shaders.iter_mut() {
-            if let Err(_) =
-                shader.create_wgpu_data(&mut store, device, texture_format, &self.pipeline_layouts)
+            if shader
+                .create_wgpu_data(&mut store, device, texture_format, &self.pipeline_layouts)
+                .is_err()
             {
                 log::debug!("Shader reload failed!");
             }
@@ -208,6 +247,33 @@ impl WgpuAssets {
 
         Ok(())
     }
+    pub(crate) fn create_tilemap(
+        &mut self,
+        material: &str,
+        params: TilemapParams,
+    ) -> Result<ResourceId, EngineError> {
+        let material_id = *self
+            .get_material_id(material)
+            .ok_or(EngineError::ResourceNotFound)?;
+        let tilemap = tilemap::Tilemap::new(material_id, params)?;
+        let tilemap_id = self.get_next_tilemap_id();
+        self.tilemaps.push(tilemap);
+        Ok(tilemap_id)
+    }
+    /// Rebuilds the buffers of the changed tilemap chunks.
+    /// Postponed until the material's atlas is created.
+    pub(crate) fn update_tilemaps(&mut self, device: &wgpu::Device) {
+        for tilemap in self.tilemaps.iter_mut() {
+            let Some(atlas) = self
+                .materials
+                .get(tilemap.material_id.0)
+                .and_then(|m| m.atlas.as_ref())
+            else {
+                continue;
+            };
+            tilemap.update_buffers(atlas, device);
+        }
+    }
     fn create_bind_group_layouts(&mut self, device: &wgpu::Device) {
         self.bind_group_layouts = bind_groups::get_bind_group_layouts(device);
     }
@@ -244,6 +310,86 @@ impl WgpuAssets {
         let asset_id = self.load_asset(path);
         self.create_texture(asset_id)
     }
+    /// Returns the texture id and the id of the target's own camera.
+    pub(crate) fn create_render_target(&mut self, w: u32, h: u32) -> (ResourceId, ResourceId) {
+        let texture_id = self.get_next_texture_id();
+        self.textures
+            .push(texture::TextureData::render_target(w, h));
+        let (w, h) = (w as f32, h as f32);
+        let camera_id = self.create_camera(w, h, w, h, 1., Vector2f::new(0.5 * w, 0.5 * h));
+        if let Some(camera) = self.cameras.last_mut() {
+            camera.set_fixed_size(true);
+        }
+        (texture_id, camera_id)
+    }
+    /// Creates the GPU data of a render target added after the surface creation.
+    pub(crate) fn create_render_target_wgpu_data(
+        &mut self,
+        texture_id: ResourceId,
+        camera_id: ResourceId,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture_format: &wgpu::TextureFormat,
+    ) -> Result<(), EngineError> {
+        self.create_texture_wgpu_data(texture_id, device, queue, texture_format)?;
+        let layout = self
+            .bind_group_layouts
+            .get(&bind_groups::BindGroupLayoutKind::Uniform)
+            .ok_or(EngineError::GraphicsInternalError)?;
+        self.cameras
+            .get_mut(camera_id.0)
+            .ok_or(EngineError::ResourceNotFound)?
+            .create_wgpu_data(device, layout);
+        Ok(())
+    }
+    pub(crate) fn texture_from_rgba(
+        &mut self,
+        w: u32,
+        h: u32,
+        data: &[u8],
+    ) -> Result<ResourceId, EngineError> {
+        let texture = texture::TextureData::from_rgba(w, h, data)?;
+        let texture_id = self.get_next_texture_id();
+        self.textures.push(texture);
+        Ok(texture_id)
+    }
+    /// Creates the persistent GPU texture of a texture added after the
+    /// surface creation.
+    pub(crate) fn create_texture_wgpu_data(
+        &mut self,
+        texture_id: ResourceId,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture_format: &wgpu::TextureFormat,
+    ) -> Result<(), EngineError> {
+        self.textures
+            .get_mut(texture_id.0)
+            .ok_or(EngineError::ResourceNotFound)?
+            .create_gpu_texture(device, queue, texture_format);
+        Ok(())
+    }
+    pub(crate) fn update_texture(
+        &mut self,
+        texture_id: ResourceId,
+        region: (u32, u32, u32, u32),
+        data: &[u8],
+        queue: Option<&wgpu::Queue>,
+    ) -> Result<(), EngineError> {
+        self.textures
+            .get_mut(texture_id.0)
+            .ok_or(EngineError::ResourceNotFound)?
+            .update_region(region, data, queue)
+    }
+    pub(crate) fn get_render_target_view(
+        &self,
+        texture_id: ResourceId,
+    ) -> Option<wgpu::TextureView> {
+        self.textures
+            .get(texture_id.0)?
+            .gpu_texture
+            .as_ref()
+            .map(|t| t.create_view(&wgpu::TextureViewDescriptor::default()))
+    }
     fn texture_from_bytes(&mut self, bytes: &'static [u8]) -> ResourceId {
         let asset_id = {
             let mut store = self
@@ -297,6 +443,7 @@ impl WgpuAssets {
             rows,
             cols,
             padding,
+            margin: None,
         });
 
         let params = MaterialParams {
@@ -329,6 +476,12 @@ impl WgpuAssets {
     pub fn get_camera_mut(&mut self, id: ResourceId) -> Option<&mut camera::Camera2D> {
         self.cameras.get_mut(id.0)
     }
+    pub fn get_tilemap(&self, id: ResourceId) -> Option<&tilemap::Tilemap> {
+        self.tilemaps.get(id.0)
+    }
+    pub fn get_tilemap_mut(&mut self, id: ResourceId) -> Option<&mut tilemap::Tilemap> {
+        self.tilemaps.get_mut(id.0)
+    }
     pub fn get_postprocess_id(&self, name: &str) -> Option<&ResourceId> {
         self.postprocess_names.get(name)
     }
@@ -343,7 +496,9 @@ impl WgpuAssets {
             .asset_store
             .lock()
             .expect("Can't acquire the asset store!");
-        store.load(path).expect(&format!("Can't load {}!", path))
+        store
+            .load(path)
+            .unwrap_or_else(|_| panic!("Can't load {}!", path))
     }
     fn load_builtin_shader(
         &mut self,
@@ -376,4 +531,7 @@ impl WgpuAssets {
     fn get_next_camera_id(&self) -> ResourceId {
         ResourceId(self.cameras.len())
     }
+    fn get_next_tilemap_id(&self) -> ResourceId {
+        ResourceId(self.tilemaps.len())
+    }
 }
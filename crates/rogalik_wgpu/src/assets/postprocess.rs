@@ -52,29 +52,41 @@ impl PostProcessPass {
         );
         Ok(())
     }
+    /// Renders the pass over the whole `output`, or - if the `viewport` is
+    /// given - into the viewport rect (x, y, w, h with the top-left origin)
+    /// only, with the rest of the `output` cleared by `clear_color`.
     pub fn render(
         &self,
         assets: &WgpuAssets,
         encoder: &mut wgpu::CommandEncoder,
         output: &wgpu::TextureView,
         uniform_bind_groups: &HashMap<UniformKind, wgpu::BindGroup>,
+        viewport: Option<(f32, f32, f32, f32)>,
+        clear_color: wgpu::Color,
     ) -> Result<(), EngineError> {
         let shader = assets
             .get_shader(self.shader_id)
             .ok_or(EngineError::GraphicsInternalError)?;
+        let load = match viewport {
+            Some(_) => wgpu::LoadOp::Clear(clear_color),
+            None => wgpu::LoadOp::Load,
+        };
         let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("PostProcess"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &output,
+                view: output,
                 resolve_target: None,
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Load,
+                    load,
                     store: wgpu::StoreOp::Store,
                 },
             })],
             depth_stencil_attachment: None,
             ..Default::default()
         });
+        if let Some((x, y, w, h)) = viewport {
+            pass.set_viewport(x, y, w, h, 0., 1.);
+        }
         pass.set_pipeline(
             shader
                 .pipeline
@@ -92,9 +104,10 @@ impl PostProcessPass {
         pass.draw(0..3, 0..1);
         Ok(())
     }
+    #[allow(clippy::too_many_arguments)]
     pub fn create_wgpu_data(
         &mut self,
-        textures: &Vec<TextureData>,
+        textures: &[TextureData],
         bind_group_layout: &wgpu::BindGroupLayout,
         w: u32,
         h: u32,
@@ -119,8 +132,9 @@ impl PostProcessPass {
         self.view = Some(view);
         Ok(())
     }
+    #[allow(clippy::too_many_arguments)]
     fn get_bind_group(
-        textures: &Vec<TextureData>,
+        textures: &[TextureData],
         uniform_data: &PostProcessUniform,
         bind_group_layout: &wgpu::BindGroupLayout,
         view: &wgpu::TextureView,
@@ -134,9 +148,7 @@ impl PostProcessPass {
             .get(texture_id.0)
             .ok_or(EngineError::ResourceNotFound)?;
 
-        let texture_view = texture
-            .to_wgpu_texture(device, queue, true)
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        let texture_view = texture.get_view(device, queue, true);
 
         let texture_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: address_mode,
@@ -187,7 +199,7 @@ impl PostProcessPass {
             uniform_buffer,
         ))
     }
-    fn get_texture_view(
+    pub(crate) fn get_texture_view(
         w: u32,
         h: u32,
         device: &wgpu::Device,
@@ -204,13 +216,16 @@ impl PostProcessPass {
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: texture_format.clone(),
+            format: *texture_format,
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
             view_formats: &[],
         });
         texture.create_view(&wgpu::TextureViewDescriptor::default())
     }
-    fn get_view_sampler(filter_mode: wgpu::FilterMode, device: &wgpu::Device) -> wgpu::Sampler {
+    pub(crate) fn get_view_sampler(
+        filter_mode: wgpu::FilterMode,
+        device: &wgpu::Device,
+    ) -> wgpu::Sampler {
         device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
@@ -225,7 +240,7 @@ impl PostProcessPass {
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Default, bytemuck::NoUninit, bytemuck::Zeroable)]
-struct PostProcessUniform {
+pub(crate) struct PostProcessUniform {
     pub strength: f32,
     _padding: [f32; 3], // for WASM
 }
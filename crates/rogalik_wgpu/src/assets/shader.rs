@@ -27,6 +27,9 @@ pub struct Shader {
     pub asset_id: ResourceId,
     pub kind: ShaderKind,
     pub pipeline: Option<wgpu::RenderPipeline>,
+    // post process output is blended over the target (premultiplied alpha)
+    // instead of replacing it
+    pub overlay: bool,
 }
 impl Shader {
     pub fn new(kind: ShaderKind, asset_id: ResourceId) -> Self {
@@ -34,6 +37,7 @@ impl Shader {
             asset_id,
             kind,
             pipeline: None,
+            overlay: false,
         }
     }
     pub fn create_wgpu_data(
@@ -71,6 +75,7 @@ impl Shader {
                 &shader,
                 layout,
                 texture_format,
+                self.overlay,
                 device,
             )),
         };
@@ -129,6 +134,7 @@ fn get_post_process_shader_pipeline(
     shader: &wgpu::ShaderModule,
     layout: &wgpu::PipelineLayout,
     texture_format: &wgpu::TextureFormat,
+    overlay: bool,
     device: &wgpu::Device,
 ) -> wgpu::RenderPipeline {
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
@@ -145,7 +151,10 @@ fn get_post_process_shader_pipeline(
             entry_point: Some("fs_main"),
             targets: &[Some(wgpu::ColorTargetState {
                 format: *texture_format,
-                blend: None,
+                blend: match overlay {
+                    true => Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    false => None,
+                },
                 write_mask: wgpu::ColorWrites::ALL,
             })],
             compilation_options: wgpu::PipelineCompilationOptions::default(),
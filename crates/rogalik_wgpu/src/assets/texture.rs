@@ -1,10 +1,24 @@
 use image::{GenericImageView, ImageBuffer, Rgba};
-use rogalik_common::ResourceId;
+use rogalik_common::{EngineError, ResourceId};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TextureKind {
+    // decoded from an asset, uploaded separately by each user
+    Image,
+    // created from raw pixels at runtime, can be updated
+    Dynamic,
+    RenderTarget,
+}
 
 pub(crate) struct TextureData {
-    pub asset_id: ResourceId,
+    // `None` for the textures created at runtime
+    pub asset_id: Option<ResourceId>,
     pub buffer: ImageBuffer<Rgba<u8>, Vec<u8>>,
     pub dim: (u32, u32),
+    pub kind: TextureKind,
+    // persistent GPU texture, shared by all the users
+    // (of the dynamic textures and the render targets)
+    pub gpu_texture: Option<wgpu::Texture>,
 }
 impl TextureData {
     pub fn from_bytes(asset_id: ResourceId, bytes: &[u8]) -> Self {
@@ -12,7 +26,131 @@ impl TextureData {
         Self {
             dim,
             buffer: rgba,
-            asset_id,
+            asset_id: Some(asset_id),
+            kind: TextureKind::Image,
+            gpu_texture: None,
+        }
+    }
+    /// `data`: tightly packed RGBA8 pixels (rows from top to bottom).
+    pub fn from_rgba(w: u32, h: u32, data: &[u8]) -> Result<Self, EngineError> {
+        if w == 0 || h == 0 || get_data_len(w, h) != Some(data.len()) {
+            return Err(EngineError::InvalidResource);
+        }
+        let buffer =
+            ImageBuffer::from_raw(w, h, data.to_vec()).ok_or(EngineError::InvalidResource)?;
+        Ok(Self {
+            dim: (w, h),
+            buffer,
+            asset_id: None,
+            kind: TextureKind::Dynamic,
+            gpu_texture: None,
+        })
+    }
+    pub fn render_target(w: u32, h: u32) -> Self {
+        Self {
+            dim: (w, h),
+            buffer: ImageBuffer::default(),
+            asset_id: None,
+            kind: TextureKind::RenderTarget,
+            gpu_texture: None,
+        }
+    }
+    /// Updates a region of a dynamic texture: (x, y, w, h) with the top-left
+    /// origin. The GPU texture is updated as well, if the `queue` is given.
+    pub fn update_region(
+        &mut self,
+        region: (u32, u32, u32, u32),
+        data: &[u8],
+        queue: Option<&wgpu::Queue>,
+    ) -> Result<(), EngineError> {
+        let (x, y, w, h) = region;
+        let in_bounds = x.checked_add(w).is_some_and(|x1| x1 <= self.dim.0)
+            && y.checked_add(h).is_some_and(|y1| y1 <= self.dim.1);
+        if self.kind != TextureKind::Dynamic || !in_bounds || get_data_len(w, h) != Some(data.len())
+        {
+            return Err(EngineError::InvalidResource);
+        }
+        if w == 0 || h == 0 {
+            // nothing to update
+            return Ok(());
+        }
+        let row = 4 * w as usize;
+        for (i, src) in data.chunks_exact(row).enumerate() {
+            let start = 4 * ((y as usize + i) * self.dim.0 as usize + x as usize);
+            (*self.buffer)[start..start + row].copy_from_slice(src);
+        }
+        if let (Some(texture), Some(queue)) = (&self.gpu_texture, queue) {
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d { x, y, z: 0 },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * w),
+                    rows_per_image: Some(h),
+                },
+                wgpu::Extent3d {
+                    width: w,
+                    height: h,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        Ok(())
+    }
+    /// Creates the persistent GPU texture (for the dynamic textures and the
+    /// render targets).
+    pub fn create_gpu_texture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture_format: &wgpu::TextureFormat,
+    ) {
+        match self.kind {
+            TextureKind::Image => (),
+            TextureKind::Dynamic => {
+                self.gpu_texture = Some(self.to_wgpu_texture(device, queue, false))
+            }
+            TextureKind::RenderTarget => self.create_render_target(device, texture_format),
+        }
+    }
+    fn create_render_target(
+        &mut self,
+        device: &wgpu::Device,
+        texture_format: &wgpu::TextureFormat,
+    ) {
+        self.gpu_texture = Some(device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: self.dim.0,
+                height: self.dim.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: *texture_format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            label: Some("Render Target Texture"),
+            view_formats: &[],
+        }));
+    }
+    /// Returns a view of the persistent GPU texture, if there is one.
+    /// Otherwise a new texture is uploaded from the buffer.
+    pub fn get_view(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        is_data: bool,
+    ) -> wgpu::TextureView {
+        match &self.gpu_texture {
+            Some(texture) => texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            None => self
+                .to_wgpu_texture(device, queue, is_data)
+                .create_view(&wgpu::TextureViewDescriptor::default()),
         }
     }
     pub fn update_bytes(&mut self, bytes: &[u8]) {
@@ -71,3 +209,49 @@ impl TextureData {
         (rgba, dim)
     }
 }
+
+/// Byte length of the RGBA8 pixels, `None` on overflow.
+fn get_data_len(w: u32, h: u32) -> Option<usize> {
+    // the row length has to fit `bytes_per_row` as well
+    let len = 4u32.checked_mul(w)?.checked_mul(h)?;
+    usize::try_from(len).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_region() {
+        let mut texture = TextureData::from_rgba(3, 2, &[0; 24]).unwrap();
+        texture
+            .update_region((1, 1, 2, 1), &[1, 1, 1, 1, 2, 2, 2, 2], None)
+            .unwrap();
+        assert_eq!(texture.buffer.get_pixel(1, 1), &Rgba([1, 1, 1, 1]));
+        assert_eq!(texture.buffer.get_pixel(2, 1), &Rgba([2, 2, 2, 2]));
+        assert_eq!(texture.buffer.get_pixel(0, 1), &Rgba([0, 0, 0, 0]));
+
+        // out of bounds
+        assert!(texture.update_region((2, 0, 2, 1), &[0; 8], None).is_err());
+        // invalid data length
+        assert!(TextureData::from_rgba(2, 2, &[0; 12]).is_err());
+    }
+
+    #[test]
+    fn update_region_edge_cases() {
+        let mut texture = TextureData::from_rgba(3, 2, &[0; 24]).unwrap();
+        // empty regions are a no-op
+        assert!(texture.update_region((0, 0, 0, 2), &[], None).is_ok());
+        assert!(texture.update_region((3, 2, 0, 0), &[], None).is_ok());
+        assert!(texture.update_region((4, 0, 0, 1), &[], None).is_err());
+        // overflowing sizes are rejected
+        assert!(texture
+            .update_region((u32::MAX, 0, 2, 1), &[0; 8], None)
+            .is_err());
+        assert!(texture
+            .update_region((0, 0, u32::MAX, u32::MAX), &[], None)
+            .is_err());
+        assert!(TextureData::from_rgba(u32::MAX, 2, &[]).is_err());
+        assert!(TextureData::from_rgba(0, 2, &[]).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use rogalik_common::{EngineError, ResourceId, SpriteParams, TilemapParams};
+use rogalik_math::vectors::{Vector2f, Vector2i};
+use wgpu::util::DeviceExt;
+
+use super::atlas::SpriteAtlas;
+use crate::structs::Vertex;
+
+// keeps the chunk's vertex count within the u16 index range
+const MAX_CHUNK_SIZE: u32 = 128;
+
+pub struct ChunkBuffers {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub index_count: u32,
+}
+
+#[derive(Default)]
+struct Chunk {
+    // tiles changed since the buffers were built
+    dirty: bool,
+    // `None` for the empty chunks
+    buffers: Option<ChunkBuffers>,
+}
+
+impl Chunk {
+    // the changed chunks are rebuilt before the rendering
+    fn is_empty(&self) -> bool {
+        !self.dirty && self.buffers.is_none()
+    }
+}
+
+struct TilemapLayer {
+    z_index: i32,
+    tiles: Vec<Option<usize>>,
+    chunks: Vec<Chunk>,
+}
+
+/// Grid of atlas sprites, drawn with a single material.
+/// The tiles are stored in square chunks, each with its own GPU buffers -
+/// rebuilt only when the chunk's tiles change.
+pub struct Tilemap {
+    pub material_id: ResourceId,
+    params: TilemapParams,
+    chunks_x: u32,
+    chunks_y: u32,
+    layers: Vec<TilemapLayer>,
+}
+impl Tilemap {
+    pub fn new(material_id: ResourceId, params: TilemapParams) -> Result<Self, EngineError> {
+        if params.chunk_size == 0 || params.chunk_size > MAX_CHUNK_SIZE {
+            return Err(EngineError::InvalidResource);
+        }
+        let chunks_x = params.width.div_ceil(params.chunk_size);
+        let chunks_y = params.height.div_ceil(params.chunk_size);
+        let layers = params
+            .layers
+            .iter()
+            .map(|&z_index| TilemapLayer {
+                z_index,
+                tiles: vec![None; (params.width * params.height) as usize],
+                chunks: (0..chunks_x * chunks_y).map(|_| Chunk::default()).collect(),
+            })
+            .collect();
+        Ok(Self {
+            material_id,
+            params,
+            chunks_x,
+            chunks_y,
+            layers,
+        })
+    }
+    pub fn get_tile(&self, layer: usize, v: Vector2i) -> Option<usize> {
+        let idx = self.get_index(v)?;
+        self.layers.get(layer)?.tiles[idx]
+    }
+    pub fn set_tile(
+        &mut self,
+        layer: usize,
+        v: Vector2i,
+        tile: Option<usize>,
+    ) -> Result<(), EngineError> {
+        let idx = self.get_index(v).ok_or(EngineError::InvalidResource)?;
+        let chunk = self.get_chunk_index(v);
+        let layer = self
+            .layers
+            .get_mut(layer)
+            .ok_or(EngineError::ResourceNotFound)?;
+        if layer.tiles[idx] != tile {
+            layer.tiles[idx] = tile;
+            layer.chunks[chunk].dirty = true;
+        }
+        Ok(())
+    }
+    pub fn get_layer_count(&self) -> usize {
+        self.layers.len()
+    }
+    pub fn get_z_index(&self, layer: usize) -> Option<i32> {
+        Some(self.layers.get(layer)?.z_index)
+    }
+    /// Indices of the (possibly) non-empty chunks overlapping the (world space) area.
+    pub fn get_visible_chunks(
+        &self,
+        layer: usize,
+        min: Vector2f,
+        max: Vector2f,
+    ) -> impl Iterator<Item = usize> + '_ {
+        let cw = self.params.chunk_size as f32 * self.params.tile_size.x;
+        let ch = self.params.chunk_size as f32 * self.params.tile_size.y;
+        let (min, max) = (min - self.params.origin, max - self.params.origin);
+        let x0 = (min.x / cw).floor().max(0.) as u32;
+        let y0 = (min.y / ch).floor().max(0.) as u32;
+        let x1 = ((max.x / cw).ceil().max(0.) as u32).min(self.chunks_x);
+        let y1 = ((max.y / ch).ceil().max(0.) as u32).min(self.chunks_y);
+        let chunks = self.layers.get(layer).map(|l| &l.chunks);
+
+        (y0..y1)
+            .flat_map(move |y| (x0..x1).map(move |x| (y * self.chunks_x + x) as usize))
+            .filter(move |&idx| chunks.is_some_and(|c| !c[idx].is_empty()))
+    }
+    pub fn get_chunk_buffers(&self, layer: usize, chunk: usize) -> Option<&ChunkBuffers> {
+        self.layers.get(layer)?.chunks.get(chunk)?.buffers.as_ref()
+    }
+    /// Rebuilds the buffers of the changed chunks.
+    pub fn update_buffers(&mut self, atlas: &SpriteAtlas, device: &wgpu::Device) {
+        for layer in self.layers.iter_mut() {
+            for (idx, chunk) in layer.chunks.iter_mut().enumerate() {
+                if !chunk.dirty {
+                    continue;
+                }
+                let cx = idx as u32 % self.chunks_x;
+                let cy = idx as u32 / self.chunks_x;
+                let (vertices, indices) = get_chunk_mesh(&self.params, &layer.tiles, atlas, cx, cy);
+                chunk.buffers = match indices.is_empty() {
+                    true => None,
+                    false => Some(create_chunk_buffers(&vertices, &indices, device)),
+                };
+                chunk.dirty = false;
+            }
+        }
+    }
+    fn get_index(&self, v: Vector2i) -> Option<usize> {
+        if v.x < 0 || v.y < 0 || v.x >= self.params.width as i32 || v.y >= self.params.height as i32
+        {
+            return None;
+        }
+        Some((v.y as u32 * self.params.width + v.x as u32) as usize)
+    }
+    fn get_chunk_index(&self, v: Vector2i) -> usize {
+        let cs = self.params.chunk_size as i32;
+        (v.y / cs * self.chunks_x as i32 + v.x / cs) as usize
+    }
+}
+
+fn get_chunk_mesh(
+    params: &TilemapParams,
+    tiles: &[Option<usize>],
+    atlas: &SpriteAtlas,
+    cx: u32,
+    cy: u32,
+) -> (Vec<Vertex>, Vec<u16>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let x0 = cx * params.chunk_size;
+    let y0 = cy * params.chunk_size;
+    for y in y0..(y0 + params.chunk_size).min(params.height) {
+        for x in x0..(x0 + params.chunk_size).min(params.width) {
+            let Some(tile) = tiles[(y * params.width + x) as usize] else {
+                continue;
+            };
+            let position = params.origin
+                + Vector2f::new(x as f32 * params.tile_size.x, y as f32 * params.tile_size.y);
+            let (v, i) =
+                atlas.get_sprite(tile, position, params.tile_size, SpriteParams::default());
+            let offset = vertices.len() as u16;
+            vertices.extend(v);
+            indices.extend(i.iter().map(|i| i + offset));
+        }
+    }
+    (vertices, indices)
+}
+
+fn create_chunk_buffers(
+    vertices: &[Vertex],
+    indices: &[u16],
+    device: &wgpu::Device,
+) -> ChunkBuffers {
+    ChunkBuffers {
+        vertex_buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Tilemap chunk vertex buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        }),
+        index_buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Tilemap chunk index buffer"),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX,
+        }),
+        index_count: indices.len() as u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tilemap() -> Tilemap {
+        Tilemap::new(
+            ResourceId(0),
+            TilemapParams {
+                width: 10,
+                height: 6,
+                tile_size: Vector2f::new(2., 2.),
+                chunk_size: 4,
+                layers: vec![0, 5],
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn set_tiles() {
+        let mut tilemap = tilemap();
+        tilemap.set_tile(1, Vector2i::new(9, 5), Some(3)).unwrap();
+        assert_eq!(tilemap.get_tile(1, Vector2i::new(9, 5)), Some(3));
+        assert_eq!(tilemap.get_tile(0, Vector2i::new(9, 5)), None);
+        assert!(tilemap.set_tile(0, Vector2i::new(10, 0), Some(1)).is_err());
+        assert!(tilemap.set_tile(2, Vector2i::new(0, 0), Some(1)).is_err());
+
+        // only the containing chunk is marked for the rebuild
+        let dirty = tilemap.layers[1]
+            .chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.dirty)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        assert_eq!(dirty, [5]);
+    }
+
+    #[test]
+    fn chunk_mesh() {
+        let mut tilemap = tilemap();
+        tilemap.set_tile(0, Vector2i::new(8, 4), Some(1)).unwrap();
+        tilemap.set_tile(0, Vector2i::new(9, 5), Some(2)).unwrap();
+        let atlas = SpriteAtlas::new((32, 8), 1, 4, None, None);
+
+        let (vertices, indices) =
+            get_chunk_mesh(&tilemap.params, &tilemap.layers[0].tiles, &atlas, 2, 1);
+        assert_eq!(vertices.len(), 8);
+        assert_eq!(indices, [0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7]);
+        assert_eq!(vertices[4].position, [18., 10., 0.]);
+
+        let (vertices, _) = get_chunk_mesh(&tilemap.params, &tilemap.layers[0].tiles, &atlas, 0, 0);
+        assert!(vertices.is_empty());
+    }
+}
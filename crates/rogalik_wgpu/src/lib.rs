@@ -5,9 +5,10 @@ use std::sync::{
 use winit::window::Window;
 
 use rogalik_common::{
-    traits::GraphicsSetup, BuiltInShader, EngineError, GraphicsContext, ResourceId, SpriteParams,
+    traits::GraphicsSetup, BuiltInShader, Camera, EngineError, GraphicsContext, ResourceId,
+    SpriteParams, UpscaleMode,
 };
-use rogalik_math::vectors::Vector2f;
+use rogalik_math::vectors::{Vector2f, Vector2i};
 
 mod assets;
 mod renderer2d;
@@ -23,8 +24,14 @@ const MAX_TIME: f32 = 3600.;
 // Option<Arc<Mutex<SurfaceState>>> = None;
 static SURFACE_REFRESH: AtomicBool = AtomicBool::new(false);
 
+enum RenderTarget {
+    Surface(wgpu::Surface<'static>),
+    // Offscreen target, used for headless rendering
+    Texture(wgpu::Texture),
+}
+
 struct SurfaceState {
-    surface: wgpu::Surface<'static>,
+    target: RenderTarget,
     device: wgpu::Device,
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
@@ -36,6 +43,11 @@ pub struct WgpuContext {
     clear_color: wgpu::Color,
     renderer2d: renderer2d::Renderer2d,
     rendering_resolution: Option<(u32, u32)>,
+    // camera restored when switching back from a render target
+    screen_camera_id: Option<ResourceId>,
+    // camera restored when switching back from a layer with its own camera
+    layer_camera_id: Option<ResourceId>,
+    upscale_mode: UpscaleMode,
     surface_state: Arc<Mutex<Option<SurfaceState>>>, // because of WASM
     time: f32,
 }
@@ -47,6 +59,9 @@ impl WgpuContext {
             clear_color: wgpu::Color::BLACK,
             renderer2d: renderer2d::Renderer2d::new(),
             rendering_resolution: None,
+            screen_camera_id: None,
+            layer_camera_id: None,
+            upscale_mode: UpscaleMode::default(),
             surface_state: Arc::new(Mutex::new(None)),
             time: 0.,
         }
@@ -76,8 +91,14 @@ impl WgpuContext {
     }
     fn resize_cameras(&mut self) {
         let (vw, vh, rw, rh) = self.get_current_resolutions();
-        for camera in self.assets.cameras.iter_mut() {
+        for camera in self
+            .assets
+            .cameras
+            .iter_mut()
+            .filter(|c| !c.is_fixed_size())
+        {
             camera.resize_viewport(vw as f32, vh as f32, rw as f32, rh as f32);
+            camera.set_upscale_mode(self.upscale_mode);
         }
     }
     fn post_surface_state(&mut self) {
@@ -116,6 +137,37 @@ impl WgpuContext {
             self.post_surface_state();
         }
     }
+    /// Creates the graphics context without a window.
+    /// Frames are rendered into an offscreen texture of the given size,
+    /// which can be read back with `read_pixels`.
+    /// A software (fallback) adapter is preferred, if one is available.
+    /// The context stays uninitialized (see `has_context`) if the size is
+    /// zero or no adapter is found - the reason is logged.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn create_headless_context(&mut self, w: u32, h: u32) {
+        pollster::block_on(create_headless_state(self.surface_state.clone(), w, h));
+        // Handled immediately, as there is no async surface creation
+        // in the headless mode.
+        if self.has_context() {
+            self.post_surface_state();
+        }
+    }
+    /// Reads back the last rendered frame as tightly packed RGBA8 pixels
+    /// (rows from top to bottom).
+    /// Only available in the headless mode.
+    pub fn read_pixels(&self) -> Result<Vec<u8>, EngineError> {
+        let state = self
+            .surface_state
+            .lock()
+            .map_err(|_| EngineError::GraphicsInternalError)?;
+        let state = state.as_ref().ok_or(EngineError::GraphicsNotReady)?;
+        match &state.target {
+            RenderTarget::Texture(texture) => {
+                tools::read_texture(texture, &state.device, &state.queue)
+            }
+            RenderTarget::Surface(_) => Err(EngineError::InvalidResource),
+        }
+    }
 }
 impl GraphicsSetup for WgpuContext {
     fn has_context(&self) -> bool {
@@ -132,7 +184,12 @@ impl GraphicsSetup for WgpuContext {
     }
     fn update_time(&mut self, delta: f32) {
         self.time += delta;
-        self.time = self.time % MAX_TIME;
+        self.time %= MAX_TIME;
+    }
+    fn update_cameras(&mut self, delta: f32) {
+        for camera in self.assets.cameras.iter_mut() {
+            camera.update(delta);
+        }
     }
     fn update_assets(&mut self) {
         if let Ok(state) = self.surface_state.lock() {
@@ -149,7 +206,14 @@ impl GraphicsSetup for WgpuContext {
                 if let Some(state) = state.as_mut() {
                     state.config.width = width;
                     state.config.height = height;
-                    state.surface.configure(&state.device, &state.config);
+                    match &mut state.target {
+                        RenderTarget::Surface(surface) => {
+                            surface.configure(&state.device, &state.config)
+                        }
+                        RenderTarget::Texture(texture) => {
+                            *texture = create_offscreen_texture(&state.device, &state.config)
+                        }
+                    }
                     let _ = self.renderer2d.create_wgpu_data(
                         &self.assets,
                         width,
@@ -175,16 +239,40 @@ impl GraphicsSetup for WgpuContext {
         self.handle_surface_refresh();
         if let Ok(state) = self.surface_state.lock() {
             if let Some(state) = state.as_ref() {
-                let _ = self.renderer2d.render(
-                    &self.assets,
-                    self.time,
-                    &state.surface,
-                    &state.device,
-                    &state.queue,
-                );
+                self.assets.update_tilemaps(&state.device);
+                match &state.target {
+                    RenderTarget::Surface(surface) => {
+                        let Ok(output) = surface.get_current_texture() else {
+                            return;
+                        };
+                        let _ = self.renderer2d.render(
+                            &self.assets,
+                            self.time,
+                            &output.texture,
+                            &state.device,
+                            &state.queue,
+                        );
+                        output.present();
+                    }
+                    RenderTarget::Texture(texture) => {
+                        let _ = self.renderer2d.render(
+                            &self.assets,
+                            self.time,
+                            texture,
+                            &state.device,
+                            &state.queue,
+                        );
+                    }
+                }
             }
         }
     }
+    fn begin_transition(&mut self, shader: ResourceId, progress: f32) {
+        self.renderer2d.begin_transition(shader, progress);
+    }
+    fn end_transition(&mut self) {
+        self.renderer2d.end_transition();
+    }
     fn toggle_recording(&mut self) {
         self.renderer2d.toggle_recording();
     }
@@ -217,9 +305,41 @@ impl GraphicsContext for WgpuContext {
         }
         self.resize_cameras();
     }
+    fn set_upscale_mode(&mut self, mode: UpscaleMode) {
+        self.upscale_mode = mode;
+        self.renderer2d.set_upscale_mode(mode);
+        self.resize_cameras();
+    }
     fn load_texture(&mut self, path: &str) -> ResourceId {
         self.assets.texture_from_path(path)
     }
+    fn create_texture(&mut self, w: u32, h: u32, data: &[u8]) -> Result<ResourceId, EngineError> {
+        let texture_id = self.assets.texture_from_rgba(w, h, data)?;
+        if let Ok(state) = self.surface_state.lock() {
+            if let Some(state) = state.as_ref() {
+                self.assets.create_texture_wgpu_data(
+                    texture_id,
+                    &state.device,
+                    &state.queue,
+                    &state.config.format,
+                )?;
+            }
+        }
+        Ok(texture_id)
+    }
+    fn update_texture(
+        &mut self,
+        id: ResourceId,
+        region: (u32, u32, u32, u32),
+        data: &[u8],
+    ) -> Result<(), EngineError> {
+        let state = self
+            .surface_state
+            .lock()
+            .map_err(|_| EngineError::GraphicsInternalError)?;
+        self.assets
+            .update_texture(id, region, data, state.as_ref().map(|s| &s.queue))
+    }
     fn load_material(&mut self, name: &str, params: rogalik_common::MaterialParams) {
         self.assets.create_material(name, params);
         // TODO if self.surface_state build bind_group
@@ -243,6 +363,73 @@ impl GraphicsContext for WgpuContext {
     fn add_post_process(&mut self, name: &str, params: rogalik_common::PostProcessParams) {
         self.assets.create_post_process(name, params);
     }
+    fn create_layer(&mut self, name: &str, params: rogalik_common::LayerParams) {
+        if let Err(e) = self.renderer2d.create_layer(&self.assets, name, params) {
+            log::error!("Can't create layer {}: {:?}", name, e);
+            return;
+        }
+        if let Ok(state) = self.surface_state.lock() {
+            if let Some(state) = state.as_ref() {
+                let _ = self.renderer2d.create_layers_wgpu_data(
+                    &self.assets,
+                    state.config.width,
+                    state.config.height,
+                    &state.device,
+                    &state.queue,
+                    &state.config.format,
+                );
+            }
+        }
+    }
+    fn create_render_target(&mut self, w: u32, h: u32) -> ResourceId {
+        let (texture_id, camera_id) = self.assets.create_render_target(w, h);
+        self.renderer2d
+            .add_render_target(texture_id, camera_id, w, h);
+        if let Ok(state) = self.surface_state.lock() {
+            if let Some(state) = state.as_ref() {
+                let _ = self.assets.create_render_target_wgpu_data(
+                    texture_id,
+                    camera_id,
+                    &state.device,
+                    &state.queue,
+                    &state.config.format,
+                );
+            }
+        }
+        texture_id
+    }
+    fn set_render_target(&mut self, target: Option<ResourceId>) -> Result<(), EngineError> {
+        match self.renderer2d.set_render_target(target)? {
+            Some(camera_id) => {
+                if self.screen_camera_id.is_none() {
+                    self.screen_camera_id = Some(self.current_camera_id);
+                }
+                self.current_camera_id = camera_id;
+            }
+            None => {
+                if let Some(camera_id) = self.screen_camera_id.take() {
+                    self.current_camera_id = camera_id;
+                }
+            }
+        }
+        Ok(())
+    }
+    fn set_layer(&mut self, name: &str) -> Result<(), EngineError> {
+        match self.renderer2d.set_layer(name)? {
+            Some(camera_id) => {
+                if self.layer_camera_id.is_none() {
+                    self.layer_camera_id = Some(self.current_camera_id);
+                }
+                self.current_camera_id = camera_id;
+            }
+            None => {
+                if let Some(camera_id) = self.layer_camera_id.take() {
+                    self.current_camera_id = camera_id;
+                }
+            }
+        }
+        Ok(())
+    }
     fn draw_sprite(
         &mut self,
         material: &str,
@@ -329,6 +516,32 @@ impl GraphicsContext for WgpuContext {
             z_index,
         )
     }
+    fn create_tilemap(
+        &mut self,
+        material: &str,
+        params: rogalik_common::TilemapParams,
+    ) -> Result<ResourceId, EngineError> {
+        self.assets.create_tilemap(material, params)
+    }
+    fn set_tile(
+        &mut self,
+        tilemap: ResourceId,
+        layer: usize,
+        position: Vector2i,
+        tile: Option<usize>,
+    ) -> Result<(), EngineError> {
+        self.assets
+            .get_tilemap_mut(tilemap)
+            .ok_or(EngineError::ResourceNotFound)?
+            .set_tile(layer, position, tile)
+    }
+    fn get_tile(&self, tilemap: ResourceId, layer: usize, position: Vector2i) -> Option<usize> {
+        self.assets.get_tilemap(tilemap)?.get_tile(layer, position)
+    }
+    fn draw_tilemap(&mut self, tilemap: ResourceId) -> Result<(), EngineError> {
+        self.renderer2d
+            .draw_tilemap(&self.assets, tilemap, self.current_camera_id)
+    }
     fn set_ambient(&mut self, color: rogalik_common::Color) {
         self.renderer2d.set_ambient(color);
     }
@@ -367,8 +580,13 @@ impl GraphicsContext for WgpuContext {
     }
     fn create_camera(&mut self, scale: f32, target: Vector2f) -> ResourceId {
         let (vw, vh, rw, rh) = self.get_current_resolutions();
-        self.assets
-            .create_camera(vw as f32, vh as f32, rw as f32, rh as f32, scale, target)
+        let id = self
+            .assets
+            .create_camera(vw as f32, vh as f32, rw as f32, rh as f32, scale, target);
+        if let Some(camera) = self.assets.cameras.last_mut() {
+            camera.set_upscale_mode(self.upscale_mode);
+        }
+        id
     }
     fn set_camera(&mut self, id: &ResourceId) {
         self.current_camera_id = *id;
@@ -419,19 +637,7 @@ async fn create_surface_state(
         .expect("Request for adapter failed!");
 
     log::debug!("Creating WGPU device");
-
-    let (device, queue) = adapter
-        .request_device(
-            &wgpu::DeviceDescriptor {
-                required_features: wgpu::Features::empty(),
-                required_limits: get_limits(),
-                label: None,
-                memory_hints: Default::default(),
-            },
-            None,
-        )
-        .await
-        .expect("Could not create the device!");
+    let (device, queue) = request_device(&adapter).await;
 
     log::debug!("Config WGPU surface");
     let surface_caps = surface.get_capabilities(&adapter);
@@ -479,7 +685,7 @@ async fn create_surface_state(
 
     if let Ok(mut state) = surface_state.lock() {
         *state = Some(SurfaceState {
-            surface,
+            target: RenderTarget::Surface(surface),
             device,
             queue,
             config,
@@ -489,6 +695,103 @@ async fn create_surface_state(
     SURFACE_REFRESH.store(true, Ordering::Relaxed);
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+async fn create_headless_state(surface_state: Arc<Mutex<Option<SurfaceState>>>, w: u32, h: u32) {
+    log::debug!("Creating headless WGPU instance");
+    if w == 0 || h == 0 {
+        log::error!("Invalid headless frame size: {}x{}!", w, h);
+        return;
+    }
+
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: get_backends(),
+        ..Default::default()
+    });
+
+    log::debug!("Creating WGPU adapter");
+    let mut adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::default(),
+            compatible_surface: None,
+            force_fallback_adapter: true,
+        })
+        .await;
+    if adapter.is_none() {
+        log::debug!("Fallback adapter not found, requesting the default one");
+        adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter: false,
+            })
+            .await;
+    }
+    let Some(adapter) = adapter else {
+        log::error!("No WGPU adapter available for headless rendering!");
+        return;
+    };
+    log::debug!("WGPU adapter: {:?}", adapter.get_info());
+
+    log::debug!("Creating WGPU device");
+    let (device, queue) = request_device(&adapter).await;
+
+    let config = wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        width: w,
+        height: h,
+        present_mode: wgpu::PresentMode::AutoNoVsync,
+        alpha_mode: wgpu::CompositeAlphaMode::Auto,
+        view_formats: vec![],
+        desired_maximum_frame_latency: 2,
+    };
+    let texture = create_offscreen_texture(&device, &config);
+
+    if let Ok(mut state) = surface_state.lock() {
+        *state = Some(SurfaceState {
+            target: RenderTarget::Texture(texture),
+            device,
+            queue,
+            config,
+        });
+    };
+}
+
+fn create_offscreen_texture(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Offscreen Texture"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: config.usage,
+        view_formats: &[],
+    })
+}
+
+async fn request_device(adapter: &wgpu::Adapter) -> (wgpu::Device, wgpu::Queue) {
+    adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                required_features: wgpu::Features::empty(),
+                required_limits: get_limits(),
+                label: None,
+                memory_hints: Default::default(),
+            },
+            None,
+        )
+        .await
+        .expect("Could not create the device!")
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 fn get_backends() -> wgpu::Backends {
     wgpu::Backends::all()
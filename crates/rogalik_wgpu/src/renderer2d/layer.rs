@@ -0,0 +1,94 @@
+use rogalik_common::{EngineError, LayerParams, PostProcessParams, ResourceId};
+
+use super::sprite_pass::SpritePass;
+use crate::assets::{postprocess::PostProcessPass, WgpuAssets};
+
+/// Named render layer, with its own draw queues and post process chain.
+pub struct RenderLayer {
+    pub name: String,
+    pub params: LayerParams,
+    pub sprite_pass: SpritePass,
+    // outgoing frame of a scene transition
+    pub transition_sprite_pass: SpritePass,
+    // blends the layer over the ones below, `None` for the default layer
+    // (which is rendered directly into the output)
+    pub composite_pass: Option<PostProcessPass>,
+}
+impl RenderLayer {
+    pub fn new(name: &str, params: LayerParams, clear_color: wgpu::Color) -> Self {
+        Self {
+            name: name.to_string(),
+            params,
+            sprite_pass: SpritePass::new(clear_color),
+            transition_sprite_pass: SpritePass::new(clear_color),
+            composite_pass: None,
+        }
+    }
+    /// Creates a layer composited over the layers below.
+    /// It is cleared with a transparent color.
+    pub fn new_composited(
+        name: &str,
+        params: LayerParams,
+        assets: &WgpuAssets,
+    ) -> Result<Self, EngineError> {
+        let shader_id = assets
+            .builtin_shaders
+            .get(&rogalik_common::BuiltInShader::Composite)
+            .ok_or(EngineError::GraphicsInternalError)?;
+        let mut layer = Self::new(name, LayerParams::default(), wgpu::Color::TRANSPARENT);
+        layer.composite_pass = Some(PostProcessPass::new(
+            assets.default_diffuse,
+            PostProcessParams {
+                shader: *shader_id,
+                filtering: rogalik_common::TextureFiltering::Nearest,
+                ..Default::default()
+            },
+        ));
+        layer.set_params(params);
+        Ok(layer)
+    }
+    pub fn set_params(&mut self, params: LayerParams) {
+        self.sprite_pass.native_resolution = !params.upscale;
+        self.transition_sprite_pass.native_resolution = !params.upscale;
+        self.params = params;
+    }
+    pub fn is_upscaled(&self) -> bool {
+        self.params.upscale
+    }
+    /// Ids of the post processes assigned to the layer.
+    pub fn get_post_process_ids<'a>(
+        &'a self,
+        assets: &'a WgpuAssets,
+    ) -> impl Iterator<Item = ResourceId> + 'a {
+        self.params
+            .post_process
+            .iter()
+            .filter_map(|name| assets.get_postprocess_id(name).copied())
+    }
+    pub fn create_wgpu_data(
+        &mut self,
+        assets: &WgpuAssets,
+        w: u32,
+        h: u32,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture_format: &wgpu::TextureFormat,
+    ) -> Result<(), EngineError> {
+        let Some(pass) = self.composite_pass.as_mut() else {
+            return Ok(());
+        };
+        let postprocess_layout = assets
+            .bind_group_layouts
+            .get(&crate::assets::bind_groups::BindGroupLayoutKind::PostProcess)
+            .ok_or(EngineError::GraphicsInternalError)?;
+        pass.create_wgpu_data(
+            &assets.textures,
+            postprocess_layout,
+            w,
+            h,
+            device,
+            queue,
+            texture_format,
+        )
+    }
+}
@@ -1,36 +1,160 @@
-use rogalik_common::{Color, EngineError, PostProcessParams, ResourceId, SpriteParams};
+use rogalik_common::{
+    Camera, Color, EngineError, LayerParams, PostProcessParams, ResourceId, SpriteParams,
+    UpscaleMode, DEFAULT_LAYER,
+};
 use rogalik_math::vectors::Vector2f;
+use std::collections::HashMap;
 
 use crate::assets::{material::Material, postprocess::PostProcessPass, WgpuAssets};
-use crate::structs::BindParams;
+use crate::structs::{BindParams, TilemapChunk};
+use uniforms::UniformKind;
 
+mod layer;
+mod render_target;
 mod sprite_pass;
+mod transition;
 pub(crate) mod uniforms;
 
 const MAX_LIGHTS: u32 = 16;
 
 pub struct Renderer2d {
-    sprite_pass: sprite_pass::SpritePass,
+    // composited in order, the default one first
+    layers: Vec<layer::RenderLayer>,
+    current_layer: usize,
+    render_targets: Vec<render_target::RenderTargetPass>,
+    // draw calls are directed to the render target, if set
+    current_target: Option<usize>,
+    transition_pass: transition::TransitionPass,
+    // shader and progress, reset every frame
+    transition: Option<(ResourceId, f32)>,
+    draw_to_transition: bool,
     #[cfg(feature = "video")]
     recorder: crate::tools::Recorder,
     rendering_resolution: Option<(u32, u32)>, // for pixel perfect renders
     upscale_pass: Option<PostProcessPass>,    // for pixel perfect renders
+    upscale_mode: UpscaleMode,
     uniforms: uniforms::Uniforms,
 }
 impl Renderer2d {
     pub fn new() -> Self {
-        let sprite_pass = sprite_pass::SpritePass::new(wgpu::Color::BLACK);
+        let default_layer = layer::RenderLayer::new(
+            DEFAULT_LAYER,
+            LayerParams {
+                upscale: true,
+                ..Default::default()
+            },
+            wgpu::Color::BLACK,
+        );
         Self {
-            sprite_pass,
+            layers: vec![default_layer],
+            current_layer: 0,
+            render_targets: Vec::new(),
+            current_target: None,
+            transition_pass: transition::TransitionPass::new(),
+            transition: None,
+            draw_to_transition: false,
             #[cfg(feature = "video")]
             recorder: crate::tools::Recorder::default(),
             rendering_resolution: None,
             upscale_pass: None,
+            upscale_mode: UpscaleMode::default(),
             uniforms: uniforms::Uniforms::default(),
         }
     }
     pub fn set_clear_color(&mut self, color: wgpu::Color) {
-        self.sprite_pass.clear_color = color;
+        // other layers are cleared with a transparent color
+        let layer = &mut self.layers[0];
+        layer.sprite_pass.clear_color = color;
+        layer.transition_sprite_pass.clear_color = color;
+    }
+    /// Adds a layer on top of the existing ones, or updates its params.
+    pub fn create_layer(
+        &mut self,
+        assets: &WgpuAssets,
+        name: &str,
+        params: LayerParams,
+    ) -> Result<(), EngineError> {
+        if let Some(layer) = self.layers.iter_mut().find(|l| l.name == name) {
+            layer.set_params(params);
+            return Ok(());
+        }
+        self.layers
+            .push(layer::RenderLayer::new_composited(name, params, assets)?);
+        Ok(())
+    }
+    /// Returns the layer's camera, if set.
+    pub fn set_layer(&mut self, name: &str) -> Result<Option<ResourceId>, EngineError> {
+        let idx = self
+            .layers
+            .iter()
+            .position(|l| l.name == name)
+            .ok_or(EngineError::ResourceNotFound)?;
+        self.current_layer = idx;
+        Ok(self.layers[idx].params.camera)
+    }
+    pub fn add_render_target(
+        &mut self,
+        texture_id: ResourceId,
+        camera_id: ResourceId,
+        w: u32,
+        h: u32,
+    ) {
+        self.render_targets
+            .push(render_target::RenderTargetPass::new(
+                texture_id, camera_id, w, h,
+            ));
+    }
+    /// Returns the target's camera.
+    pub fn set_render_target(
+        &mut self,
+        texture_id: Option<ResourceId>,
+    ) -> Result<Option<ResourceId>, EngineError> {
+        let Some(texture_id) = texture_id else {
+            self.current_target = None;
+            return Ok(None);
+        };
+        let idx = self
+            .render_targets
+            .iter()
+            .position(|t| t.texture_id == texture_id)
+            .ok_or(EngineError::ResourceNotFound)?;
+        self.current_target = Some(idx);
+        Ok(Some(self.render_targets[idx].camera_id))
+    }
+    pub fn create_layers_wgpu_data(
+        &mut self,
+        assets: &WgpuAssets,
+        w: u32,
+        h: u32,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture_format: &wgpu::TextureFormat,
+    ) -> Result<(), EngineError> {
+        for layer in self.layers.iter_mut() {
+            layer.create_wgpu_data(assets, w, h, device, queue, texture_format)?;
+        }
+        Ok(())
+    }
+    /// Subsequent draw calls are queued for the outgoing frame of the
+    /// transition, which is blended with the main frame by `shader_id`.
+    /// The transition is active for the current frame only.
+    pub fn begin_transition(&mut self, shader_id: ResourceId, progress: f32) {
+        self.transition = Some((shader_id, progress));
+        self.draw_to_transition = true;
+    }
+    /// Subsequent draw calls are queued for the main frame again.
+    pub fn end_transition(&mut self) {
+        self.draw_to_transition = false;
+    }
+    fn sprite_queue(&mut self) -> &mut sprite_pass::SpritePass {
+        if let Some(idx) = self.current_target {
+            return &mut self.render_targets[idx].sprite_pass;
+        }
+        let layer = &mut self.layers[self.current_layer];
+        match self.draw_to_transition {
+            true => &mut layer.transition_sprite_pass,
+            false => &mut layer.sprite_pass,
+        }
     }
     pub fn resize(&mut self, w: u32, h: u32) {
         self.uniforms.globals.viewport_size = [w as f32, h as f32];
@@ -60,6 +184,16 @@ impl Renderer2d {
         self.uniforms.globals.render_size = [w as f32, h as f32];
         Ok(())
     }
+    pub fn set_upscale_mode(&mut self, mode: UpscaleMode) {
+        self.upscale_mode = mode;
+    }
+    // target area of the upscale pass: (x, y, w, h) with the top-left origin
+    fn get_upscale_viewport(&self) -> (f32, f32, f32, f32) {
+        let [vw, vh] = self.uniforms.globals.viewport_size;
+        let [rw, rh] = self.uniforms.globals.render_size;
+        let (origin, size) = self.upscale_mode.get_rect(vw, vh, rw, rh);
+        (origin.x, vh - origin.y - size.y, size.x, size.y)
+    }
     pub fn create_upscale_pass(
         &mut self,
         assets: &WgpuAssets,
@@ -114,6 +248,17 @@ impl Renderer2d {
     ) -> Result<(), EngineError> {
         log::debug!("Creating Renderer2d data with w:{}, h:{}", width, height);
         self.create_upscale_pass(assets, device, queue, texture_format)?;
+        self.create_layers_wgpu_data(assets, width, height, device, queue, texture_format)?;
+        self.transition_pass.create_wgpu_data(
+            assets
+                .bind_group_layouts
+                .get(&crate::assets::bind_groups::BindGroupLayoutKind::PostProcess)
+                .ok_or(EngineError::GraphicsInternalError)?,
+            width,
+            height,
+            device,
+            texture_format,
+        );
         self.uniforms.create_wgpu_data(
             assets
                 .bind_group_layouts
@@ -123,6 +268,7 @@ impl Renderer2d {
         );
         Ok(())
     }
+    #[allow(clippy::too_many_arguments)]
     pub fn draw_atlas_sprite(
         &mut self,
         assets: &WgpuAssets,
@@ -142,23 +288,24 @@ impl Renderer2d {
             shader_id: material.shader_id,
         };
 
-        if let Some(_) = params.slice {
+        if params.slice.is_some() {
             let s = material
                 .atlas
                 .ok_or(EngineError::InvalidResource)?
                 .get_sliced_sprite(index, position, size, params);
-            self.sprite_pass
+            self.sprite_queue()
                 .add_to_queue(&s.0, &s.1, z_index, bind_params);
         } else {
             let s = material
                 .atlas
                 .ok_or(EngineError::InvalidResource)?
                 .get_sprite(index, position, size, params);
-            self.sprite_pass
+            self.sprite_queue()
                 .add_to_queue(&s.0, &s.1, z_index, bind_params);
         };
         Ok(())
     }
+    #[allow(clippy::too_many_arguments)]
     pub fn draw_text(
         &mut self,
         assets: &WgpuAssets,
@@ -180,11 +327,57 @@ impl Renderer2d {
         };
 
         for s in crate::assets::font::get_text_sprites(text, atlas, position, size, params) {
-            self.sprite_pass
+            self.sprite_queue()
                 .add_to_queue(&s.0, &s.1, z_index, bind_params);
         }
         Ok(())
     }
+    /// Queues the layers' chunks overlapping the camera's view.
+    pub fn draw_tilemap(
+        &mut self,
+        assets: &WgpuAssets,
+        tilemap_id: ResourceId,
+        camera_id: ResourceId,
+    ) -> Result<(), EngineError> {
+        let tilemap = assets
+            .get_tilemap(tilemap_id)
+            .ok_or(EngineError::ResourceNotFound)?;
+        let material = assets
+            .get_material(tilemap.material_id)
+            .ok_or(EngineError::ResourceNotFound)?;
+        let camera = assets
+            .get_camera(camera_id)
+            .ok_or(EngineError::ResourceNotFound)?;
+
+        let (min, max) = camera.get_bounds();
+        // the shake is applied to the rendered view only
+        let shake = camera
+            .get_controller()
+            .map(|c| c.get_shake_offset())
+            .unwrap_or(Vector2f::ZERO);
+        let margin = Vector2f::new(shake.x.abs(), shake.y.abs());
+        let params = BindParams {
+            camera_id,
+            material_id: tilemap.material_id,
+            shader_id: material.shader_id,
+        };
+
+        for layer in 0..tilemap.get_layer_count() {
+            let z_index = tilemap
+                .get_z_index(layer)
+                .ok_or(EngineError::GraphicsInternalError)?;
+            for chunk in tilemap.get_visible_chunks(layer, min - margin, max + margin) {
+                self.sprite_queue().add_tilemap_chunk(TilemapChunk {
+                    tilemap_id,
+                    layer,
+                    chunk,
+                    z_index,
+                    params,
+                });
+            }
+        }
+        Ok(())
+    }
     pub fn draw_mesh(
         &mut self,
         assets: &WgpuAssets,
@@ -201,7 +394,7 @@ impl Renderer2d {
             material_id,
             shader_id: material.shader_id,
         };
-        self.sprite_pass
+        self.sprite_queue()
             .add_to_queue(vertices, indices, z_index, bind_params);
 
         Ok(())
@@ -211,7 +404,7 @@ impl Renderer2d {
         &mut self,
         assets: &WgpuAssets,
         time: f32,
-        surface: &wgpu::Surface,
+        target: &wgpu::Texture,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
     ) -> Result<(), EngineError> {
@@ -222,58 +415,71 @@ impl Renderer2d {
         self.uniforms.globals.time = time;
         self.uniforms.write_buffers(queue)?;
 
-        let output = surface
-            .get_current_texture()
-            .map_err(|_| EngineError::GraphicsNotReady)?;
-
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
 
         let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Renderer2D Encoder"),
         });
 
-        // TODO avoid allocation here?
-        let mut post_process_queue = Vec::new();
-        if let Some(pass) = &self.upscale_pass {
-            post_process_queue.push(pass);
+        // rendered first, so the materials using them get the current content
+        for target in self.render_targets.iter_mut() {
+            target.render(assets, &mut encoder, device, &self.uniforms.bind_groups)?;
         }
-        post_process_queue.extend(
-            assets
-                .postprocess
-                .iter()
-                .filter(|p| p.get_strength() > 0.001),
-        );
 
-        let mut current_view = if let Some(pass) = post_process_queue.first() {
-            pass.get_view().ok_or(EngineError::GraphicsNotReady)?
-        } else {
-            &view
-        };
-
-        self.sprite_pass.render(
-            assets,
-            &mut encoder,
-            device,
-            &self.uniforms.bind_groups,
-            current_view,
-        )?;
+        let upscale = self
+            .upscale_pass
+            .as_ref()
+            .map(|pass| (pass, Some(self.get_upscale_viewport())));
 
-        let mut post_processes = post_process_queue.iter().peekable();
-        while let Some(pass) = post_processes.next() {
-            current_view = if let Some(next_pass) = post_processes.peek() {
-                next_pass.get_view().ok_or(EngineError::GraphicsNotReady)?
-            } else {
-                &view
-            };
-            pass.render(
-                assets,
-                &mut encoder,
-                current_view,
-                &self.uniforms.bind_groups,
-            )?;
+        match self.transition.take() {
+            Some((shader_id, progress)) => {
+                self.transition_pass.write_buffer(progress, queue)?;
+                render_layers(
+                    &mut self.layers,
+                    true,
+                    upscale,
+                    assets,
+                    &mut encoder,
+                    device,
+                    &self.uniforms.bind_groups,
+                    self.transition_pass
+                        .get_from_view()
+                        .ok_or(EngineError::GraphicsNotReady)?,
+                )?;
+                render_layers(
+                    &mut self.layers,
+                    false,
+                    upscale,
+                    assets,
+                    &mut encoder,
+                    device,
+                    &self.uniforms.bind_groups,
+                    self.transition_pass
+                        .get_to_view()
+                        .ok_or(EngineError::GraphicsNotReady)?,
+                )?;
+                self.transition_pass.render(
+                    shader_id,
+                    assets,
+                    &mut encoder,
+                    &view,
+                    &self.uniforms.bind_groups,
+                )?;
+            }
+            None => {
+                render_layers(
+                    &mut self.layers,
+                    false,
+                    upscale,
+                    assets,
+                    &mut encoder,
+                    device,
+                    &self.uniforms.bind_groups,
+                    &view,
+                )?;
+            }
         }
+        self.draw_to_transition = false;
 
         queue.submit(std::iter::once(encoder.finish()));
 
@@ -284,11 +490,10 @@ impl Renderer2d {
                 self.uniforms.globals.viewport_size[1] as u32,
                 device,
                 queue,
-                &output,
+                target,
             );
         }
 
-        output.present();
         self.uniforms.lights.frame_end();
         Ok(())
     }
@@ -298,6 +503,115 @@ impl Renderer2d {
     }
 }
 
+/// Post process pass with an optional output viewport.
+type QueuedPostProcess<'a> = (&'a PostProcessPass, Option<(f32, f32, f32, f32)>);
+
+/// Renders and composites the layers into `output`.
+/// `transition`: render the outgoing frame's queues.
+#[allow(clippy::too_many_arguments)]
+fn render_layers(
+    layers: &mut [layer::RenderLayer],
+    transition: bool,
+    upscale: Option<QueuedPostProcess>,
+    assets: &WgpuAssets,
+    encoder: &mut wgpu::CommandEncoder,
+    device: &wgpu::Device,
+    uniform_bind_groups: &HashMap<UniformKind, wgpu::BindGroup>,
+    output: &wgpu::TextureView,
+) -> Result<(), EngineError> {
+    // post processes assigned to the other layers are skipped by the default one
+    let assigned = layers
+        .iter()
+        .flat_map(|l| l.get_post_process_ids(assets))
+        .collect::<Vec<_>>();
+
+    for layer in layers.iter_mut() {
+        // TODO avoid allocation here?
+        let mut post_process_queue = Vec::new();
+        if layer.is_upscaled() {
+            post_process_queue.extend(upscale);
+        }
+        match &layer.composite_pass {
+            Some(composite_pass) => {
+                post_process_queue.extend(
+                    layer
+                        .get_post_process_ids(assets)
+                        .filter_map(|id| assets.postprocess.get(id.0))
+                        .filter(|p| p.get_strength() > 0.001)
+                        .map(|p| (p, None)),
+                );
+                post_process_queue.push((composite_pass, None));
+            }
+            None => post_process_queue.extend(
+                assets
+                    .postprocess
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, p)| {
+                        !assigned.contains(&ResourceId(*i)) && p.get_strength() > 0.001
+                    })
+                    .map(|(_, p)| (p, None)),
+            ),
+        }
+
+        let sprite_pass = match transition {
+            true => &mut layer.transition_sprite_pass,
+            false => &mut layer.sprite_pass,
+        };
+        if layer.composite_pass.is_some() && sprite_pass.is_empty() {
+            // nothing to blend over the layers below
+            continue;
+        }
+        render_frame(
+            sprite_pass,
+            &post_process_queue,
+            assets,
+            encoder,
+            device,
+            uniform_bind_groups,
+            output,
+        )?;
+    }
+    Ok(())
+}
+
+/// Renders the sprite queue through the post process chain into `output`.
+fn render_frame(
+    sprite_pass: &mut sprite_pass::SpritePass,
+    post_process_queue: &[QueuedPostProcess],
+    assets: &WgpuAssets,
+    encoder: &mut wgpu::CommandEncoder,
+    device: &wgpu::Device,
+    uniform_bind_groups: &HashMap<UniformKind, wgpu::BindGroup>,
+    output: &wgpu::TextureView,
+) -> Result<(), EngineError> {
+    let mut current_view = if let Some((pass, _)) = post_process_queue.first() {
+        pass.get_view().ok_or(EngineError::GraphicsNotReady)?
+    } else {
+        output
+    };
+
+    sprite_pass.render(assets, encoder, device, uniform_bind_groups, current_view)?;
+
+    let mut post_processes = post_process_queue.iter().peekable();
+    while let Some((pass, viewport)) = post_processes.next() {
+        current_view = if let Some((next_pass, _)) = post_processes.peek() {
+            next_pass.get_view().ok_or(EngineError::GraphicsNotReady)?
+        } else {
+            output
+        };
+        pass.render(
+            assets,
+            encoder,
+            current_view,
+            uniform_bind_groups,
+            *viewport,
+            sprite_pass.clear_color,
+        )?;
+    }
+    Ok(())
+}
+
 fn get_material<'a>(
     name: &str,
     assets: &'a WgpuAssets,
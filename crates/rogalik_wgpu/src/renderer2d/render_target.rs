@@ -0,0 +1,42 @@
+use rogalik_common::{EngineError, ResourceId};
+use std::collections::HashMap;
+
+use super::{sprite_pass::SpritePass, uniforms::UniformKind};
+use crate::assets::WgpuAssets;
+
+/// Offscreen render target, with its own draw queue.
+/// The target texture is stored with the other textures, so it can be used
+/// by the materials.
+pub struct RenderTargetPass {
+    pub texture_id: ResourceId,
+    pub camera_id: ResourceId,
+    pub sprite_pass: SpritePass,
+}
+impl RenderTargetPass {
+    pub fn new(texture_id: ResourceId, camera_id: ResourceId, w: u32, h: u32) -> Self {
+        let mut sprite_pass = SpritePass::new(wgpu::Color::TRANSPARENT);
+        sprite_pass.target_size = Some((w, h));
+        Self {
+            texture_id,
+            camera_id,
+            sprite_pass,
+        }
+    }
+    pub fn render(
+        &mut self,
+        assets: &WgpuAssets,
+        encoder: &mut wgpu::CommandEncoder,
+        device: &wgpu::Device,
+        uniform_bind_groups: &HashMap<UniformKind, wgpu::BindGroup>,
+    ) -> Result<(), EngineError> {
+        if self.sprite_pass.is_empty() {
+            // nothing drawn this frame - the previous content is kept
+            return Ok(());
+        }
+        let view = assets
+            .get_render_target_view(self.texture_id)
+            .ok_or(EngineError::GraphicsNotReady)?;
+        self.sprite_pass
+            .render(assets, encoder, device, uniform_bind_groups, &view)
+    }
+}
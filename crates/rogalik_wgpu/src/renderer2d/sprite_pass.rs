@@ -1,16 +1,22 @@
-use rogalik_common::EngineError;
+use rogalik_common::{EngineError, ResourceId};
 use std::collections::HashMap;
 use wgpu::util::DeviceExt;
 
 use crate::assets::WgpuAssets;
-use crate::structs::{BindParams, Triangle, Vertex};
+use crate::structs::{BindParams, TilemapChunk, Triangle, Vertex};
 
 use super::uniforms::UniformKind;
 
 pub struct SpritePass {
     pub clear_color: wgpu::Color,
+    // rendered at the window resolution, regardless of the rendering one
+    pub native_resolution: bool,
+    // fixed size of the target (eg. render targets), the cameras' viewports
+    // are mapped onto it
+    pub target_size: Option<(u32, u32)>,
     vertex_queue: Vec<Vertex>,
     triangle_queue: Vec<Triangle>,
+    tilemap_queue: Vec<TilemapChunk>,
     // pipeline: wgpu::RenderPipeline,
     // pub bind_group_layout: wgpu::BindGroupLayout,
 }
@@ -18,8 +24,11 @@ impl SpritePass {
     pub fn new(clear_color: wgpu::Color) -> Self {
         Self {
             clear_color,
+            native_resolution: false,
+            target_size: None,
             vertex_queue: Vec::new(),
             triangle_queue: Vec::new(),
+            tilemap_queue: Vec::new(),
         }
     }
     pub fn add_to_queue(
@@ -39,6 +48,12 @@ impl SpritePass {
                 params,
             }))
     }
+    pub fn add_tilemap_chunk(&mut self, chunk: TilemapChunk) {
+        self.tilemap_queue.push(chunk);
+    }
+    pub fn is_empty(&self) -> bool {
+        self.triangle_queue.is_empty() && self.tilemap_queue.is_empty()
+    }
     pub fn render(
         &mut self,
         assets: &WgpuAssets,
@@ -47,11 +62,82 @@ impl SpritePass {
         uniform_bind_groups: &HashMap<UniformKind, wgpu::BindGroup>,
         view: &wgpu::TextureView,
     ) -> Result<(), EngineError> {
-        if self.triangle_queue.len() == 0 {
+        if self.is_empty() {
             self.vertex_queue.clear();
             return Ok(());
         };
 
+        let buffers = match self.triangle_queue.is_empty() {
+            true => None,
+            false => Some(self.create_buffers(device)),
+        };
+        // stable, so the chunks keep the queued (layer) order
+        self.tilemap_queue.sort_by_key(|c| c.z_index);
+
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Sprite Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(self.clear_color),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                ..Default::default()
+            });
+            pass.set_bind_group(2, uniform_bind_groups.get(&UniformKind::Globals), &[]);
+            pass.set_bind_group(3, uniform_bind_groups.get(&UniformKind::Lights), &[]);
+
+            let mut chunks = self.tilemap_queue.iter().peekable();
+            // `None` after a chunk is drawn, as it changes the bindings
+            let mut current_params = None;
+            let mut batch_start = 0;
+
+            for (i, tri) in self.triangle_queue.iter().enumerate() {
+                let offset = 3 * i as u32;
+
+                // chunks are drawn below the sprites of the same z_index
+                while let Some(chunk) = chunks.next_if(|c| c.z_index <= tri.z_index) {
+                    if current_params.take().is_some() {
+                        pass.draw_indexed(batch_start..offset, 0, 0..1);
+                    }
+                    self.draw_chunk(&mut pass, assets, chunk)?;
+                }
+
+                if current_params != Some(tri.params) {
+                    if current_params.is_some() {
+                        // draw the previous batch first
+                        pass.draw_indexed(batch_start..offset, 0, 0..1);
+                    } else if let Some((vertex_buffer, index_buffer)) = &buffers {
+                        pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                        pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                    }
+                    self.bind(&mut pass, assets, tri.params, current_params)?;
+                    current_params = Some(tri.params);
+                    batch_start = offset;
+                }
+            }
+            if current_params.is_some() {
+                pass.draw_indexed(batch_start..3 * self.triangle_queue.len() as u32, 0, 0..1);
+            }
+            for chunk in chunks {
+                self.draw_chunk(&mut pass, assets, chunk)?;
+            }
+        }
+        // let start = std::time::Instant::now();
+        // output.present();
+        // println!("Present: {:?}, {}", start.elapsed(), counter);
+
+        self.vertex_queue.clear();
+        self.triangle_queue.clear();
+        self.tilemap_queue.clear();
+        Ok(())
+    }
+    /// Sorts the triangles and creates the vertex and index buffers.
+    fn create_buffers(&mut self, device: &wgpu::Device) -> (wgpu::Buffer, wgpu::Buffer) {
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Sprite vertex buffer"),
             contents: bytemuck::cast_slice(&self.vertex_queue),
@@ -71,8 +157,7 @@ impl SpritePass {
         let indices = self
             .triangle_queue
             .iter()
-            .map(|t| t.indices)
-            .flatten()
+            .flat_map(|t| t.indices)
             .collect::<Vec<_>>();
 
         let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -80,108 +165,111 @@ impl SpritePass {
             contents: bytemuck::cast_slice(&indices),
             usage: wgpu::BufferUsages::INDEX,
         });
-
-        {
-            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Sprite Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(self.clear_color),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                ..Default::default()
-            });
-
-            let mut offset = 0;
-            let mut batch_start = 0;
-            let mut current_params = self.triangle_queue[0].params;
-
+        (vertex_buffer, index_buffer)
+    }
+    /// Sets the bindings differing from the `current` ones (all if `None`).
+    fn bind(
+        &self,
+        pass: &mut wgpu::RenderPass,
+        assets: &WgpuAssets,
+        params: BindParams,
+        current: Option<BindParams>,
+    ) -> Result<(), EngineError> {
+        if current.is_none_or(|c| c.shader_id != params.shader_id) {
             let pipeline = assets
-                .get_shader(current_params.shader_id)
+                .get_shader(params.shader_id)
                 .ok_or(EngineError::GraphicsInternalError)?
                 .pipeline
                 .as_ref()
                 .ok_or(EngineError::GraphicsNotReady)?;
             pass.set_pipeline(pipeline);
-
+        }
+        if current.is_none_or(|c| c.material_id != params.material_id) {
             let bind_group = assets
-                .get_material(current_params.material_id)
+                .get_material(params.material_id)
                 .ok_or(EngineError::GraphicsInternalError)?
                 .bind_group
                 .as_ref()
                 .ok_or(EngineError::GraphicsNotReady)?;
             pass.set_bind_group(0, bind_group, &[]);
-
-            pass.set_bind_group(
-                1,
-                assets
-                    .cameras
-                    .get(current_params.camera_id.0)
-                    .ok_or(EngineError::ResourceNotFound)?
-                    .get_bind_group()
-                    .ok_or(EngineError::GraphicsNotReady)?,
-                &[],
-            );
-            pass.set_bind_group(2, uniform_bind_groups.get(&UniformKind::Globals), &[]);
-            pass.set_bind_group(3, uniform_bind_groups.get(&UniformKind::Lights), &[]);
-
-            pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-            pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-
-            for tri in self.triangle_queue.iter() {
-                let end = offset + 3;
-
-                if current_params != tri.params {
-                    // draw the previous batch first
-                    pass.draw_indexed(batch_start..offset, 0, 0..1);
-                    // counter += 1;
-                    if current_params.shader_id != tri.params.shader_id {
-                        let pipeline = assets
-                            .get_shader(tri.params.shader_id)
-                            .ok_or(EngineError::GraphicsInternalError)?
-                            .pipeline
-                            .as_ref()
-                            .ok_or(EngineError::GraphicsNotReady)?;
-                        pass.set_pipeline(pipeline);
-                    }
-                    if current_params.material_id != tri.params.material_id {
-                        let bind_group = assets
-                            .get_material(tri.params.material_id)
-                            .ok_or(EngineError::GraphicsInternalError)?
-                            .bind_group
-                            .as_ref()
-                            .ok_or(EngineError::GraphicsNotReady)?;
-                        pass.set_bind_group(0, bind_group, &[]);
-                    }
-                    if current_params.camera_id != tri.params.camera_id {
-                        pass.set_bind_group(
-                            1,
-                            assets
-                                .cameras
-                                .get(tri.params.camera_id.0)
-                                .ok_or(EngineError::ResourceNotFound)?
-                                .get_bind_group()
-                                .ok_or(EngineError::GraphicsNotReady)?,
-                            &[],
-                        );
-                    }
-                    current_params = tri.params;
-                    batch_start = offset;
-                }
-                offset = end;
-            }
-            pass.draw_indexed(batch_start..offset, 0, 0..1);
         }
-        // let start = std::time::Instant::now();
-        // output.present();
-        // println!("Present: {:?}, {}", start.elapsed(), counter);
-
-        self.vertex_queue.clear();
-        self.triangle_queue.clear();
+        if current.is_none_or(|c| c.camera_id != params.camera_id) {
+            set_camera(
+                pass,
+                assets,
+                params.camera_id,
+                self.native_resolution,
+                self.target_size,
+            )?;
+        }
         Ok(())
     }
+    fn draw_chunk(
+        &self,
+        pass: &mut wgpu::RenderPass,
+        assets: &WgpuAssets,
+        chunk: &TilemapChunk,
+    ) -> Result<(), EngineError> {
+        let Some(buffers) = assets
+            .tilemaps
+            .get(chunk.tilemap_id.0)
+            .and_then(|t| t.get_chunk_buffers(chunk.layer, chunk.chunk))
+        else {
+            // emptied after being queued
+            return Ok(());
+        };
+        self.bind(pass, assets, chunk.params, None)?;
+        pass.set_vertex_buffer(0, buffers.vertex_buffer.slice(..));
+        pass.set_index_buffer(buffers.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        pass.draw_indexed(0..buffers.index_count, 0, 0..1);
+        Ok(())
+    }
+}
+
+/// Binds the camera and restricts the drawing to its viewport.
+fn set_camera(
+    pass: &mut wgpu::RenderPass,
+    assets: &WgpuAssets,
+    camera_id: ResourceId,
+    native_resolution: bool,
+    target_size: Option<(u32, u32)>,
+) -> Result<(), EngineError> {
+    let camera = assets
+        .cameras
+        .get(camera_id.0)
+        .ok_or(EngineError::ResourceNotFound)?;
+    pass.set_bind_group(
+        1,
+        camera
+            .get_bind_group()
+            .ok_or(EngineError::GraphicsNotReady)?,
+        &[],
+    );
+    let ((mut x, mut y, mut w, mut h), (mut rw, mut rh)) = match native_resolution {
+        true => (camera.get_window_viewport_rect(), camera.get_window_size()),
+        false => (camera.get_viewport_rect(), camera.get_render_size()),
+    };
+    if let Some((tw, th)) = target_size {
+        // eg. the screen camera drawing into a render target
+        let (tw, th) = (tw as f32, th as f32);
+        if rw > 0. && rh > 0. {
+            let (sx, sy) = (tw / rw, th / rh);
+            (x, y, w, h) = (x * sx, y * sy, w * sx, h * sy);
+        }
+        (rw, rh) = (tw, th);
+    }
+    // both rects have to be within the target (wgpu panics otherwise).
+    // the camera keeps its viewport inside, so only the rounding is cut off
+    let x0 = x.clamp(0., rw);
+    let y0 = y.clamp(0., rh);
+    let x1 = (x + w).clamp(x0, rw);
+    let y1 = (y + h).clamp(y0, rh);
+    if x1 - x0 < 1. || y1 - y0 < 1. {
+        // nothing visible
+        pass.set_scissor_rect(0, 0, 0, 0);
+        return Ok(());
+    }
+    pass.set_viewport(x0, y0, x1 - x0, y1 - y0, 0., 1.);
+    pass.set_scissor_rect(x0 as u32, y0 as u32, (x1 - x0) as u32, (y1 - y0) as u32);
+    Ok(())
 }
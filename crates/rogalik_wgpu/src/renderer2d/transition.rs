@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use wgpu::util::DeviceExt;
+
+use rogalik_common::{EngineError, ResourceId};
+
+use super::uniforms::UniformKind;
+use crate::assets::{
+    postprocess::{PostProcessPass, PostProcessUniform},
+    WgpuAssets,
+};
+
+/// Blends the outgoing and the incoming frame of a scene transition.
+/// Uses the regular post process shader layout:
+/// - binding 0: the incoming frame
+/// - binding 2: the outgoing frame
+/// - strength uniform: transition progress (0. - 1.)
+pub struct TransitionPass {
+    bind_group: Option<wgpu::BindGroup>,
+    uniform_buffer: Option<wgpu::Buffer>,
+    from_view: Option<wgpu::TextureView>,
+    to_view: Option<wgpu::TextureView>,
+}
+impl TransitionPass {
+    pub fn new() -> Self {
+        Self {
+            bind_group: None,
+            uniform_buffer: None,
+            from_view: None,
+            to_view: None,
+        }
+    }
+    pub fn get_from_view(&self) -> Option<&wgpu::TextureView> {
+        self.from_view.as_ref()
+    }
+    pub fn get_to_view(&self) -> Option<&wgpu::TextureView> {
+        self.to_view.as_ref()
+    }
+    pub fn write_buffer(&self, progress: f32, queue: &wgpu::Queue) -> Result<(), EngineError> {
+        let mut uniform_data = PostProcessUniform::new();
+        uniform_data.strength = progress;
+        queue.write_buffer(
+            self.uniform_buffer
+                .as_ref()
+                .ok_or(EngineError::GraphicsNotReady)?,
+            0,
+            bytemuck::cast_slice(&[uniform_data]),
+        );
+        Ok(())
+    }
+    pub fn render(
+        &self,
+        shader_id: ResourceId,
+        assets: &WgpuAssets,
+        encoder: &mut wgpu::CommandEncoder,
+        output: &wgpu::TextureView,
+        uniform_bind_groups: &HashMap<UniformKind, wgpu::BindGroup>,
+    ) -> Result<(), EngineError> {
+        let shader = assets
+            .get_shader(shader_id)
+            .ok_or(EngineError::ResourceNotFound)?;
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Transition"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            ..Default::default()
+        });
+        pass.set_pipeline(
+            shader
+                .pipeline
+                .as_ref()
+                .ok_or(EngineError::GraphicsNotReady)?,
+        );
+        pass.set_bind_group(
+            0,
+            self.bind_group
+                .as_ref()
+                .ok_or(EngineError::GraphicsNotReady)?,
+            &[],
+        );
+        pass.set_bind_group(1, uniform_bind_groups.get(&UniformKind::Globals), &[]);
+        pass.draw(0..3, 0..1);
+        Ok(())
+    }
+    pub fn create_wgpu_data(
+        &mut self,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        w: u32,
+        h: u32,
+        device: &wgpu::Device,
+        texture_format: &wgpu::TextureFormat,
+    ) {
+        let from_view = PostProcessPass::get_texture_view(w, h, device, texture_format);
+        let to_view = PostProcessPass::get_texture_view(w, h, device, texture_format);
+        let sampler = PostProcessPass::get_view_sampler(wgpu::FilterMode::Nearest, device);
+
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Transition Uniform Buffer"),
+            contents: bytemuck::cast_slice(&[PostProcessUniform::new()]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Transition Bind Group"),
+            layout: bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&to_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&from_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        self.bind_group = Some(bind_group);
+        self.uniform_buffer = Some(uniform_buffer);
+        self.from_view = Some(from_view);
+        self.to_view = Some(to_view);
+    }
+}
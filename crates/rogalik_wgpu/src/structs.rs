@@ -29,6 +29,16 @@ pub struct Triangle {
     pub params: BindParams,
 }
 
+/// Tilemap chunk, queued for drawing with its cached buffers.
+#[derive(Clone, Copy)]
+pub struct TilemapChunk {
+    pub tilemap_id: ResourceId,
+    pub layer: usize,
+    pub chunk: usize,
+    pub z_index: i32,
+    pub params: BindParams,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct BindParams {
     pub shader_id: ResourceId,
@@ -1,4 +1,7 @@
-pub(crate) mod recorder;
+mod readback;
+#[cfg(feature = "video")]
+mod recorder;
 
+pub(crate) use readback::read_texture;
 #[cfg(feature = "video")]
 pub(crate) use recorder::Recorder;
@@ -0,0 +1,67 @@
+use rogalik_common::EngineError;
+use std::sync::mpsc;
+
+use crate::utils::get_bytes_per_row;
+
+/// Copies an RGBA8 texture into a tightly packed pixel buffer.
+/// Blocks until the GPU work is finished.
+pub(crate) fn read_texture(
+    texture: &wgpu::Texture,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+) -> Result<Vec<u8>, EngineError> {
+    let size = texture.size();
+    let (bytes_per_row, padded_bytes_per_row) = get_bytes_per_row(size.width);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        size: (padded_bytes_per_row * size.height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        label: Some("Readback buffer"),
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Readback encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(size.height),
+            },
+        },
+        size,
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let buffer_slice = buffer.slice(..);
+    let (sender, receiver) = mpsc::channel();
+    buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    if let Err(e) = receiver
+        .recv()
+        .map_err(|_| EngineError::GraphicsInternalError)?
+    {
+        log::error!("Can't map the readback buffer: {}", e);
+        return Err(EngineError::GraphicsInternalError);
+    }
+
+    let pixels = buffer_slice
+        .get_mapped_range()
+        .chunks(padded_bytes_per_row as usize)
+        .flat_map(|row| &row[..bytes_per_row as usize])
+        .copied()
+        .collect();
+    buffer.unmap();
+    Ok(pixels)
+}
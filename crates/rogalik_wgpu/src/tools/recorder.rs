@@ -3,6 +3,8 @@ use std::{
     process::Command,
 };
 
+use crate::utils::get_bytes_per_row;
+
 #[derive(Default)]
 pub(crate) struct Recorder {
     buffer: Option<wgpu::Buffer>,
@@ -36,7 +38,7 @@ impl Recorder {
         height: u32,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-        output: &wgpu::SurfaceTexture,
+        output: &wgpu::Texture,
     ) {
         if !self.is_recording {
             return;
@@ -50,11 +52,11 @@ impl Recorder {
             label: Some("Recording encoder"),
         });
 
-        let (_, padded_bytes_per_row) = Self::get_bytes_per_row(width);
+        let (_, padded_bytes_per_row) = get_bytes_per_row(width);
 
         encoder.copy_texture_to_buffer(
             wgpu::ImageCopyTexture {
-                texture: &output.texture,
+                texture: output,
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
@@ -67,7 +69,7 @@ impl Recorder {
                     rows_per_image: Some(height),
                 },
             },
-            output.texture.size(),
+            output.size(),
         );
 
         queue.submit(std::iter::once(encoder.finish()));
@@ -96,23 +98,16 @@ impl Recorder {
         self.height = height;
     }
     fn get_buffer_size(width: u32, height: u32) -> wgpu::BufferAddress {
-        let (_, padded_bytes_per_row) = Self::get_bytes_per_row(width);
+        let (_, padded_bytes_per_row) = get_bytes_per_row(width);
         (padded_bytes_per_row * height) as wgpu::BufferAddress
     }
-    fn get_bytes_per_row(width: u32) -> (u32, u32) {
-        let pixel_size = std::mem::size_of::<[u8; 4]>() as u32;
-        let bytes_per_row = pixel_size * width;
-        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
-        let padding = (align - bytes_per_row % align) % align;
-        (bytes_per_row, bytes_per_row + padding)
-    }
     fn save_video(&mut self, path: &str) {
         let frames = std::mem::take(&mut self.frames);
         let width = self.width;
         let height = self.height;
         let path = path.to_string();
 
-        let (bytes_per_row, padded_bytes_per_row) = Self::get_bytes_per_row(width);
+        let (bytes_per_row, padded_bytes_per_row) = get_bytes_per_row(width);
 
         let _ = std::thread::spawn(move || {
             let (pipe_reader, mut pipe_writer) = pipe().unwrap();
@@ -24,3 +24,12 @@ pub(crate) fn get_wgpu_filter_mode(filtering: TextureFiltering) -> wgpu::FilterM
         TextureFiltering::Linear => wgpu::FilterMode::Linear,
     }
 }
+
+/// Returns (bytes_per_row, padded_bytes_per_row) for an RGBA8 texture copy.
+pub(crate) fn get_bytes_per_row(width: u32) -> (u32, u32) {
+    let pixel_size = std::mem::size_of::<[u8; 4]>() as u32;
+    let bytes_per_row = pixel_size * width;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let padding = (align - bytes_per_row % align) % align;
+    (bytes_per_row, bytes_per_row + padding)
+}
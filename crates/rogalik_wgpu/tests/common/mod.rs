@@ -0,0 +1,141 @@
+//! Golden-image snapshot harness.
+//!
+//! Frames are rendered headlessly (preferably on a software adapter) and
+//! compared against PNG files stored in `tests/snapshots`.
+//! Set `ROGALIK_UPDATE_SNAPSHOTS=1` to (re)generate the stored images -
+//! otherwise a missing snapshot fails the test.
+//! Without a graphics adapter the tests fail as well, unless
+//! `ROGALIK_SKIP_NO_ADAPTER=1` is set.
+//! On a mismatch the rendered frame and a diff image are written to the
+//! cargo tmp dir.
+
+use image::{ImageBuffer, Rgba, RgbaImage};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use rogalik_assets::AssetStore;
+use rogalik_common::traits::GraphicsSetup;
+use rogalik_wgpu::WgpuContext;
+
+const UPDATE_ENV: &str = "ROGALIK_UPDATE_SNAPSHOTS";
+const SKIP_ENV: &str = "ROGALIK_SKIP_NO_ADAPTER";
+
+/// Max allowed difference of a single color channel.
+pub const DEFAULT_TOLERANCE: u8 = 2;
+
+pub struct Snapshot {
+    pub graphics: WgpuContext,
+    width: u32,
+    height: u32,
+    tolerance: u8,
+}
+impl Snapshot {
+    /// Creates a headless graphics context.
+    /// `setup` is executed before the GPU data is created, so all the
+    /// textures, materials, shaders and cameras should be loaded there.
+    /// Returns `None` if no adapter is available and the skipping is enabled -
+    /// the calling test should return then.
+    pub fn new(width: u32, height: u32, setup: impl FnOnce(&mut WgpuContext)) -> Option<Self> {
+        let mut graphics = WgpuContext::new(Arc::new(Mutex::new(AssetStore::default())));
+        setup(&mut graphics);
+        graphics.create_headless_context(width, height);
+        if !graphics.has_context() {
+            if std::env::var(SKIP_ENV).is_err() {
+                panic!(
+                    "No graphics adapter available! Set {}=1 to skip the snapshot tests.",
+                    SKIP_ENV
+                );
+            }
+            eprintln!("No graphics adapter available, skipping the snapshot test.");
+            return None;
+        }
+        Some(Self {
+            graphics,
+            width,
+            height,
+            tolerance: DEFAULT_TOLERANCE,
+        })
+    }
+    pub fn with_tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+    /// Renders the queued draw calls and compares the output with the
+    /// stored `name` snapshot.
+    pub fn assert_frame(&mut self, name: &str) {
+        self.graphics.render();
+        let pixels = self
+            .graphics
+            .read_pixels()
+            .expect("Can't read back the frame!");
+        let actual = RgbaImage::from_raw(self.width, self.height, pixels)
+            .expect("Invalid frame dimensions!");
+
+        let path = snapshot_dir().join(format!("{}.png", name));
+        if std::env::var(UPDATE_ENV).is_ok() {
+            actual.save(&path).expect("Can't save the snapshot!");
+            eprintln!("Snapshot written: {:?}", path);
+            return;
+        }
+        assert!(
+            path.exists(),
+            "Snapshot `{}` is missing! Run with {}=1 to create it.",
+            name,
+            UPDATE_ENV
+        );
+
+        let expected = image::open(&path)
+            .expect("Can't open the snapshot!")
+            .to_rgba8();
+        assert_eq!(
+            expected.dimensions(),
+            actual.dimensions(),
+            "Snapshot `{}` has different dimensions!",
+            name
+        );
+
+        let (diff, mismatched) = diff_images(&expected, &actual, self.tolerance);
+        if mismatched == 0 {
+            return;
+        }
+
+        let out_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("snapshots");
+        std::fs::create_dir_all(&out_dir).expect("Can't create the output dir!");
+        let actual_path = out_dir.join(format!("{}.actual.png", name));
+        let diff_path = out_dir.join(format!("{}.diff.png", name));
+        actual.save(&actual_path).expect("Can't save the frame!");
+        diff.save(&diff_path).expect("Can't save the diff!");
+
+        panic!(
+            "Snapshot `{}` does not match: {} pixel(s) differ by more than {}.\nActual: {:?}\nDiff: {:?}",
+            name, mismatched, self.tolerance, actual_path, diff_path
+        );
+    }
+}
+
+/// Returns the diff image and the number of mismatched pixels.
+/// Mismatches are marked red, matching pixels are dimmed to grayscale.
+fn diff_images(expected: &RgbaImage, actual: &RgbaImage, tolerance: u8) -> (RgbaImage, usize) {
+    let mut mismatched = 0;
+    let diff = ImageBuffer::from_fn(expected.width(), expected.height(), |x, y| {
+        let e = expected.get_pixel(x, y);
+        let a = actual.get_pixel(x, y);
+        if e.0.iter().zip(a.0).any(|(e, a)| e.abs_diff(a) > tolerance) {
+            mismatched += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let luma = (e[0] as u32 + e[1] as u32 + e[2] as u32) / 3;
+            let v = (luma / 4) as u8;
+            Rgba([v, v, v, 255])
+        }
+    });
+    (diff, mismatched)
+}
+
+fn snapshot_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+}
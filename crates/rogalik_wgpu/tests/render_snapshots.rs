@@ -0,0 +1,630 @@
+use rogalik_common::traits::GraphicsSetup;
+use std::collections::HashSet;
+
+use rogalik_common::{
+    AtlasParams, BuiltInShader, Color, FogOfWar, GraphicsContext, LayerParams, MaterialParams,
+    ResourceId, SpriteParams, TilemapParams, UpscaleMode, Visibility, DEFAULT_LAYER,
+};
+use rogalik_math::vectors::{Vector2f, Vector2i};
+
+mod common;
+use common::Snapshot;
+
+const SIZE: u32 = 64;
+
+fn load_sprites(graphics: &mut rogalik_wgpu::WgpuContext) {
+    let texture = graphics.load_texture("examples/arkanoid/sprites.png");
+    graphics.load_material(
+        "sprites",
+        MaterialParams {
+            atlas: Some(AtlasParams {
+                cols: 4,
+                rows: 1,
+                padding: None,
+                margin: None,
+            }),
+            diffuse_texture: Some(texture),
+            ..Default::default()
+        },
+    );
+}
+
+fn centered_camera(graphics: &mut rogalik_wgpu::WgpuContext) {
+    graphics.create_camera(1., Vector2f::splat(0.5 * SIZE as f32));
+}
+
+#[test]
+fn atlas_sprites() {
+    let Some(mut snapshot) = Snapshot::new(SIZE, SIZE, |graphics| {
+        load_sprites(graphics);
+        centered_camera(graphics);
+    }) else {
+        return;
+    };
+
+    let size = Vector2f::splat(8.);
+    for index in 0..4 {
+        let x = 4. + 16. * index as f32;
+        let _ = snapshot.graphics.draw_atlas_sprite(
+            "sprites",
+            index,
+            Vector2f::new(x, 48.),
+            0,
+            size,
+            SpriteParams::default(),
+        );
+        let _ = snapshot.graphics.draw_atlas_sprite(
+            "sprites",
+            index,
+            Vector2f::new(x, 32.),
+            0,
+            size,
+            SpriteParams {
+                flip_x: true,
+                color: Color(255, 128, 0, 255),
+                ..Default::default()
+            },
+        );
+        let _ = snapshot.graphics.draw_atlas_sprite(
+            "sprites",
+            index,
+            Vector2f::new(x, 16.),
+            0,
+            size,
+            SpriteParams {
+                flip_y: true,
+                ..Default::default()
+            },
+        );
+        let _ = snapshot.graphics.draw_atlas_sprite(
+            "sprites",
+            index,
+            Vector2f::new(x, 0.),
+            0,
+            size,
+            SpriteParams {
+                rotate: Some(std::f32::consts::FRAC_PI_4),
+                ..Default::default()
+            },
+        );
+    }
+    snapshot.assert_frame("atlas_sprites");
+}
+
+#[test]
+fn nine_slice_sprites() {
+    let Some(mut snapshot) = Snapshot::new(SIZE, SIZE, |graphics| {
+        load_sprites(graphics);
+        centered_camera(graphics);
+    }) else {
+        return;
+    };
+
+    let slice = Some((2, Vector2f::splat(8.)));
+    let _ = snapshot.graphics.draw_atlas_sprite(
+        "sprites",
+        0,
+        Vector2f::new(4., 40.),
+        0,
+        Vector2f::new(56., 16.),
+        SpriteParams {
+            slice,
+            ..Default::default()
+        },
+    );
+    let _ = snapshot.graphics.draw_atlas_sprite(
+        "sprites",
+        1,
+        Vector2f::new(4., 20.),
+        0,
+        Vector2f::new(24., 16.),
+        SpriteParams {
+            slice,
+            flip_x: true,
+            flip_y: true,
+            ..Default::default()
+        },
+    );
+    let _ = snapshot.graphics.draw_atlas_sprite(
+        "sprites",
+        0,
+        Vector2f::new(36., 4.),
+        0,
+        Vector2f::new(24., 32.),
+        SpriteParams {
+            slice,
+            rotate: Some(0.3),
+            ..Default::default()
+        },
+    );
+    snapshot.assert_frame("nine_slice_sprites");
+}
+
+#[test]
+fn text() {
+    let Some(mut snapshot) = Snapshot::new(SIZE, SIZE, |graphics| {
+        graphics.load_font("pixel", "examples/font.png", 16, 16, Some((11., 7.)), None);
+        centered_camera(graphics);
+    }) else {
+        return;
+    };
+
+    let _ = snapshot.graphics.draw_text(
+        "pixel",
+        "Rogalik",
+        Vector2f::new(2., 40.),
+        0,
+        9.,
+        SpriteParams::default(),
+    );
+    let _ = snapshot.graphics.draw_text(
+        "pixel",
+        "0123",
+        Vector2f::new(2., 16.),
+        0,
+        18.,
+        SpriteParams {
+            color: Color(0, 255, 128, 255),
+            ..Default::default()
+        },
+    );
+    snapshot.assert_frame("text");
+}
+
+#[test]
+fn lighting() {
+    let Some(mut snapshot) = Snapshot::new(SIZE, SIZE, |graphics| {
+        let diffuse_texture = Some(graphics.load_texture("examples/lighting/diffuse.png"));
+        let normal_texture = Some(graphics.load_texture("examples/lighting/normal.png"));
+        let shader = graphics.get_builtin_shader(BuiltInShader::SpriteLit);
+        graphics.load_material(
+            "lit",
+            MaterialParams {
+                diffuse_texture,
+                normal_texture,
+                shader,
+                ..Default::default()
+            },
+        );
+        centered_camera(graphics);
+    })
+    .map(|s| s.with_tolerance(4)) else {
+        return;
+    };
+
+    snapshot.graphics.set_ambient(Color(32, 32, 64, 255));
+    for x in 0..4 {
+        for y in 0..4 {
+            let _ = snapshot.graphics.draw_sprite(
+                "lit",
+                16. * Vector2f::new(x as f32, y as f32),
+                0,
+                Vector2f::splat(16.),
+                SpriteParams::default(),
+            );
+        }
+    }
+    let _ = snapshot
+        .graphics
+        .add_light(Vector2f::new(16., 40.), 24., Color(255, 128, 0, 255), 0.5);
+    let _ = snapshot
+        .graphics
+        .add_light(Vector2f::new(48., 16.), 32., Color(128, 0, 255, 255), 0.8);
+    snapshot.assert_frame("lighting");
+}
+
+#[test]
+fn upscale_post_process() {
+    let Some(mut snapshot) = Snapshot::new(SIZE, SIZE, |graphics| {
+        load_sprites(graphics);
+        graphics.create_camera(1., Vector2f::splat(8.));
+        graphics.set_rendering_resolution(16, 16);
+    }) else {
+        return;
+    };
+
+    snapshot.graphics.set_clear_color(Color(32, 64, 96, 255));
+    let _ = snapshot.graphics.draw_atlas_sprite(
+        "sprites",
+        2,
+        Vector2f::new(4., 4.),
+        0,
+        Vector2f::splat(8.),
+        SpriteParams::default(),
+    );
+    snapshot.assert_frame("upscale_post_process");
+}
+
+#[test]
+fn upscale_letterbox() {
+    let Some(mut snapshot) = Snapshot::new(SIZE, 48, |graphics| {
+        load_sprites(graphics);
+        graphics.create_camera(1., Vector2f::splat(8.));
+        graphics.set_rendering_resolution(16, 16);
+    }) else {
+        return;
+    };
+
+    // 3x scale, with 8px bars on the sides
+    snapshot.graphics.set_upscale_mode(UpscaleMode::Integer);
+    snapshot.graphics.set_clear_color(Color(32, 64, 96, 255));
+    let _ = snapshot.graphics.draw_atlas_sprite(
+        "sprites",
+        2,
+        Vector2f::ZERO,
+        0,
+        Vector2f::splat(16.),
+        SpriteParams::default(),
+    );
+    snapshot.assert_frame("upscale_letterbox");
+}
+
+#[test]
+fn render_layers() {
+    let Some(mut snapshot) = Snapshot::new(SIZE, SIZE, |graphics| {
+        load_sprites(graphics);
+        graphics.create_camera(1., Vector2f::splat(8.));
+        graphics.set_rendering_resolution(16, 16);
+        graphics.create_layer("ui", LayerParams::default());
+    }) else {
+        return;
+    };
+
+    snapshot.graphics.set_clear_color(Color(32, 64, 96, 255));
+    let _ = snapshot.graphics.draw_atlas_sprite(
+        "sprites",
+        2,
+        Vector2f::new(4., 4.),
+        0,
+        Vector2f::splat(8.),
+        SpriteParams::default(),
+    );
+    // drawn at the native resolution, over the upscaled default layer
+    snapshot.graphics.set_layer("ui").unwrap();
+    let _ = snapshot.graphics.draw_atlas_sprite(
+        "sprites",
+        1,
+        Vector2f::new(2., 2.),
+        -10,
+        Vector2f::splat(4.),
+        SpriteParams::default(),
+    );
+    snapshot.graphics.set_layer(DEFAULT_LAYER).unwrap();
+    snapshot.assert_frame("render_layers");
+}
+
+#[test]
+fn layer_camera_restored() {
+    let Some(mut snapshot) = Snapshot::new(SIZE, SIZE, |graphics| {
+        load_sprites(graphics);
+        centered_camera(graphics);
+        let zoomed = graphics.create_camera(4., Vector2f::splat(4.));
+        graphics.create_layer(
+            "zoomed",
+            LayerParams {
+                camera: Some(zoomed),
+                ..Default::default()
+            },
+        );
+    }) else {
+        return;
+    };
+
+    snapshot.graphics.set_clear_color(Color(32, 64, 96, 255));
+    snapshot.graphics.set_layer("zoomed").unwrap();
+    let _ = snapshot.graphics.draw_atlas_sprite(
+        "sprites",
+        1,
+        Vector2f::ZERO,
+        0,
+        Vector2f::splat(8.),
+        SpriteParams::default(),
+    );
+    // drawn with the centered camera again
+    snapshot.graphics.set_layer(DEFAULT_LAYER).unwrap();
+    let _ = snapshot.graphics.draw_atlas_sprite(
+        "sprites",
+        2,
+        Vector2f::splat(48.),
+        0,
+        Vector2f::splat(16.),
+        SpriteParams::default(),
+    );
+    snapshot.assert_frame("layer_camera_restored");
+}
+
+#[test]
+fn render_to_texture() {
+    let mut target = ResourceId::default();
+    let Some(mut snapshot) = Snapshot::new(SIZE, SIZE, |graphics| {
+        load_sprites(graphics);
+        centered_camera(graphics);
+        target = graphics.create_render_target(16, 16);
+        graphics.load_material(
+            "target",
+            MaterialParams {
+                diffuse_texture: Some(target),
+                ..Default::default()
+            },
+        );
+    }) else {
+        return;
+    };
+
+    snapshot.graphics.set_clear_color(Color(32, 64, 96, 255));
+    snapshot.graphics.set_render_target(Some(target)).unwrap();
+    for (i, x) in [0., 8.].iter().enumerate() {
+        let _ = snapshot.graphics.draw_atlas_sprite(
+            "sprites",
+            i + 1,
+            Vector2f::new(*x, *x),
+            0,
+            Vector2f::splat(8.),
+            SpriteParams::default(),
+        );
+    }
+    snapshot.graphics.set_render_target(None).unwrap();
+    for x in [0., 32.] {
+        let _ = snapshot.graphics.draw_sprite(
+            "target",
+            Vector2f::new(x, x),
+            0,
+            Vector2f::splat(32.),
+            SpriteParams::default(),
+        );
+    }
+    snapshot.assert_frame("render_to_texture");
+}
+
+#[test]
+fn render_to_texture_screen_camera() {
+    let mut target = ResourceId::default();
+    let mut camera = ResourceId::default();
+    let Some(mut snapshot) = Snapshot::new(SIZE, SIZE, |graphics| {
+        load_sprites(graphics);
+        camera = graphics.create_camera(1., Vector2f::splat(0.5 * SIZE as f32));
+        target = graphics.create_render_target(16, 16);
+        graphics.load_material(
+            "target",
+            MaterialParams {
+                diffuse_texture: Some(target),
+                ..Default::default()
+            },
+        );
+    }) else {
+        return;
+    };
+
+    snapshot.graphics.set_clear_color(Color(32, 64, 96, 255));
+    // the screen camera's view is mapped onto the (smaller) target
+    snapshot.graphics.set_render_target(Some(target)).unwrap();
+    snapshot.graphics.set_camera(&camera);
+    let _ = snapshot.graphics.draw_atlas_sprite(
+        "sprites",
+        2,
+        Vector2f::new(16., 16.),
+        0,
+        Vector2f::splat(32.),
+        SpriteParams::default(),
+    );
+    snapshot.graphics.set_render_target(None).unwrap();
+    let _ = snapshot.graphics.draw_sprite(
+        "target",
+        Vector2f::ZERO,
+        0,
+        Vector2f::splat(32.),
+        SpriteParams::default(),
+    );
+    snapshot.assert_frame("render_to_texture_screen_camera");
+}
+
+#[test]
+fn runtime_texture() {
+    let mut texture = ResourceId::default();
+    let Some(mut snapshot) = Snapshot::new(SIZE, SIZE, |graphics| {
+        centered_camera(graphics);
+        // 2x2 checker
+        texture = graphics
+            .create_texture(
+                2,
+                2,
+                &[
+                    255, 0, 0, 255, 0, 0, 0, 255, //
+                    0, 0, 0, 255, 0, 0, 255, 255,
+                ],
+            )
+            .unwrap();
+        graphics.load_material(
+            "runtime",
+            MaterialParams {
+                diffuse_texture: Some(texture),
+                ..Default::default()
+            },
+        );
+    }) else {
+        return;
+    };
+
+    // top-right pixel updated after the upload
+    snapshot
+        .graphics
+        .update_texture(texture, (1, 0, 1, 1), &[0, 255, 0, 255])
+        .unwrap();
+    let _ = snapshot.graphics.draw_sprite(
+        "runtime",
+        Vector2f::ZERO,
+        0,
+        Vector2f::splat(SIZE as f32),
+        SpriteParams::default(),
+    );
+    snapshot.assert_frame("runtime_texture");
+}
+
+#[test]
+fn fog_of_war() {
+    let mut fog = FogOfWar::new(4, 4, 16.);
+    let Some(mut snapshot) = Snapshot::new(SIZE, SIZE, |graphics| {
+        load_sprites(graphics);
+        centered_camera(graphics);
+        fog.create(graphics, "fog").unwrap();
+    }) else {
+        return;
+    };
+
+    fog.set(Vector2i::new(0, 3), Visibility::Explored);
+    fog.update_visible(&HashSet::from([Vector2i::new(1, 1), Vector2i::new(2, 1)]));
+    for index in 0..4 {
+        let _ = snapshot.graphics.draw_atlas_sprite(
+            "sprites",
+            index,
+            Vector2f::new(16. * index as f32, 0.),
+            0,
+            Vector2f::new(16., SIZE as f32),
+            SpriteParams::default(),
+        );
+    }
+    fog.draw(&mut snapshot.graphics, 10).unwrap();
+    snapshot.assert_frame("fog_of_war");
+}
+
+#[test]
+fn tilemap() {
+    let mut tilemap = ResourceId::default();
+    let Some(mut snapshot) = Snapshot::new(SIZE, SIZE, |graphics| {
+        load_sprites(graphics);
+        centered_camera(graphics);
+        tilemap = graphics
+            .create_tilemap(
+                "sprites",
+                TilemapParams {
+                    // partially outside of the view
+                    width: 10,
+                    height: 8,
+                    tile_size: Vector2f::splat(8.),
+                    layers: vec![0, 2],
+                    chunk_size: 4,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+    }) else {
+        return;
+    };
+
+    let graphics = &mut snapshot.graphics;
+    for y in 0..8 {
+        for x in 0..10 {
+            let _ = graphics.set_tile(tilemap, 0, Vector2i::new(x, y), Some((x + y) as usize % 2));
+        }
+        let _ = graphics.set_tile(tilemap, 1, Vector2i::new(y, y), Some(2));
+    }
+    let _ = graphics.set_tile(tilemap, 0, Vector2i::new(0, 0), None);
+    let _ = graphics.draw_tilemap(tilemap);
+    // above the layer of the same z_index
+    let _ = graphics.draw_atlas_sprite(
+        "sprites",
+        0,
+        Vector2f::new(24., 24.),
+        2,
+        Vector2f::splat(8.),
+        SpriteParams {
+            color: Color(255, 0, 0, 255),
+            ..Default::default()
+        },
+    );
+    snapshot.assert_frame("tilemap");
+}
+
+#[test]
+fn transition_wipe() {
+    let Some(mut snapshot) = Snapshot::new(SIZE, SIZE, |graphics| {
+        load_sprites(graphics);
+        centered_camera(graphics);
+    }) else {
+        return;
+    };
+
+    let shader = snapshot
+        .graphics
+        .get_builtin_shader(BuiltInShader::Wipe)
+        .unwrap();
+    snapshot.graphics.begin_transition(shader, 0.5);
+    let _ = snapshot.graphics.draw_atlas_sprite(
+        "sprites",
+        1,
+        Vector2f::ZERO,
+        0,
+        Vector2f::splat(SIZE as f32),
+        SpriteParams::default(),
+    );
+    snapshot.graphics.end_transition();
+    let _ = snapshot.graphics.draw_atlas_sprite(
+        "sprites",
+        2,
+        Vector2f::ZERO,
+        0,
+        Vector2f::splat(SIZE as f32),
+        SpriteParams::default(),
+    );
+    snapshot.assert_frame("transition_wipe");
+}
+
+#[test]
+fn camera_viewports() {
+    let Some(mut snapshot) = Snapshot::new(SIZE, SIZE, |graphics| {
+        load_sprites(graphics);
+        // left half - regular camera
+        let left = graphics.create_camera(1., Vector2f::splat(8.));
+        graphics
+            .get_camera_mut(&left)
+            .unwrap()
+            .set_viewport(Some((Vector2f::ZERO, Vector2f::new(0.5, 1.))));
+        // right half - zoomed out and rotated
+        let right = graphics.create_camera(0.5, Vector2f::splat(8.));
+        let camera = graphics.get_camera_mut(&right).unwrap();
+        camera.set_viewport(Some((Vector2f::new(0.5, 0.), Vector2f::new(0.5, 1.))));
+        camera.set_rotation(std::f32::consts::FRAC_PI_4);
+    }) else {
+        return;
+    };
+
+    snapshot.graphics.set_clear_color(Color(32, 64, 96, 255));
+    for id in 0..2 {
+        snapshot.graphics.set_camera(&ResourceId(id));
+        let _ = snapshot.graphics.draw_atlas_sprite(
+            "sprites",
+            2,
+            Vector2f::ZERO,
+            0,
+            Vector2f::splat(16.),
+            SpriteParams::default(),
+        );
+    }
+    snapshot.assert_frame("camera_viewports");
+}
+
+#[test]
+fn camera_viewport_outside() {
+    // the part outside of the target is cut off
+    let Some(mut snapshot) = Snapshot::new(SIZE, SIZE, |graphics| {
+        load_sprites(graphics);
+        let camera = graphics.create_camera(1., Vector2f::splat(8.));
+        graphics
+            .get_camera_mut(&camera)
+            .unwrap()
+            .set_viewport(Some((Vector2f::new(0.75, 0.), Vector2f::new(0.5, 1.))));
+    }) else {
+        return;
+    };
+
+    snapshot.graphics.set_clear_color(Color(32, 64, 96, 255));
+    let _ = snapshot.graphics.draw_atlas_sprite(
+        "sprites",
+        2,
+        Vector2f::ZERO,
+        0,
+        Vector2f::splat(16.),
+        SpriteParams::default(),
+    );
+    snapshot.assert_frame("camera_viewport_outside");
+}